@@ -1,8 +1,10 @@
+use crate::spawning::EnemyType;
 use bevy::asset::Handle;
 use bevy::audio::AudioSource;
 use bevy::image::Image;
 use bevy::prelude::{Resource, TextureAtlasLayout};
 use bevy_asset_loader::asset_collection::AssetCollection;
+use std::collections::HashMap;
 
 #[derive(AssetCollection, Resource)]
 pub struct SwordAnimation {
@@ -161,6 +163,17 @@ pub struct CutSceneSounds {
     pub draw_sword: Handle<AudioSource>,
 }
 
+//index of an upgrade level into a table with len entries
+//a level past the end (broken or hand edited save) gets the last entry instead of panicking
+pub fn level_index(len: usize, level: i32) -> Option<usize> {
+    let last = len.checked_sub(1)?;
+    return Some((level.max(0) as usize).min(last));
+}
+
+pub fn level_value<T: Copy>(values: &[T], level: i32, fallback: T) -> T {
+    return level_index(values.len(), level).map_or(fallback, |index| values[index]);
+}
+
 #[derive(serde::Deserialize)]
 pub struct ShopDisplay {
     pub cost: i32,
@@ -180,6 +193,17 @@ pub struct GameInfos {
     pub arise_cooldown: Vec<f32>,
     pub arise_count: Vec<i32>,
     pub attack_cooldown: Vec<f32>,
+    pub crit_chance: Vec<f32>,
+    pub crit_multiplier: Vec<f32>,
+    //skeleton damage is rolled between these times the damage upgrade
+    pub damage_roll: [f32; 2],
+    //seconds until a corpse is gone
+    pub corpse_decay: Vec<f32>,
+    //a fallen skeleton leaves its corpse again until it was raised this often
+    pub max_resurrections: u32,
+    pub sword_health: Vec<f32>,
+    //health per second the sword gets back while no hero is around
+    pub sword_repair: Vec<f32>,
 }
 // #[derive(Resource)]
 // struct LevelHandle(Handle<crate::Level>);
@@ -187,6 +211,220 @@ pub struct GameInfos {
 pub struct GameData {
     #[asset(path = "game.data.json")]
     pub data: Handle<GameInfos>,
+    #[asset(path = "game.enemies.json")]
+    pub enemies: Handle<EnemyInfos>,
+}
+
+#[derive(serde::Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum EnemySheet {
+    Hero,
+    Boss,
+}
+#[derive(serde::Deserialize)]
+pub struct EnemySpriteInfo {
+    pub sheet: EnemySheet,
+    pub pixels_per_metre: f32,
+    pub pivot: [f32; 2],
+}
+#[derive(serde::Deserialize)]
+pub struct AnimationInfo {
+    pub start: usize,
+    pub end: usize,
+    pub repeating: bool,
+    //0 means the animation never advances on its own (single frame)
+    #[serde(default)]
+    pub frame_time: f32,
+}
+#[derive(serde::Deserialize)]
+pub struct HitterInfo {
+    pub knockback: f32,
+    pub min_damage: f32,
+    pub max_damage: f32,
+    #[serde(default)]
+    pub crit_chance: f32,
+    #[serde(default = "default_crit_multiplier")]
+    pub crit_multiplier: f32,
+    pub hit_box: [f32; 2],
+    pub offset: [f32; 2],
+}
+fn default_crit_multiplier() -> f32 {
+    return 1.0;
+}
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnemyBehavior {
+    Basic {
+        cooldown_time: f32,
+        stun_time: f32,
+    },
+    Boss {
+        walk_time: f32,
+        //seconds between the steps of a pattern
+        stomp_delay_delta: f32,
+        //telegraphed attacks by name, the attack pool refers to them
+        patterns: HashMap<String, BossPatternInfo>,
+        //needed when the pool has the charge in it
+        #[serde(default)]
+        charge: Option<BossChargeInfo>,
+        //attack pool until the first phase starts
+        #[serde(default = "default_boss_attacks")]
+        attacks: Vec<BossAttackWeight>,
+        #[serde(default)]
+        phases: Vec<BossPhaseInfo>,
+    },
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BossAttack {
+    Hit,
+    //name of one of the boss patterns
+    Pattern(String),
+    Charge,
+}
+
+//the boss marks a lane towards the sword and dashes through it
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct BossChargeInfo {
+    pub windup: f32,
+    pub recover: f32,
+    pub speed: f32,
+    pub distance: f32,
+    pub knockback: f32,
+    #[serde(default)]
+    pub damage: f32,
+    pub hit_box: [f32; 2],
+}
+
+//a telegraphed attack, every strike marks the ground first and hits after its delay
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct BossPatternInfo {
+    //boss winds up this long, then recovers this long before walking again
+    pub windup: f32,
+    pub recover: f32,
+    //seconds from the start until a strike on step 0 hits
+    pub delay: f32,
+    //the whole pattern is shifted by a random amount up to this, 0 keeps it in place
+    #[serde(default)]
+    pub random_offset: f32,
+    //seconds a strike stays around after it started
+    pub lifetime: f32,
+    //size of the telegraph sprite
+    #[serde(default = "default_pattern_scale")]
+    pub scale: f32,
+    //used by strikes that don't set their own
+    pub hit_box: [f32; 2],
+    pub damage: f32,
+    #[serde(default)]
+    pub knockback: f32,
+    pub strikes: Vec<StrikeInfo>,
+}
+fn default_pattern_scale() -> f32 {
+    return 1.0;
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct StrikeInfo {
+    //relative to the boss
+    pub position: f32,
+    //the telegraph shows after step times the delay delta and hits that much later as well
+    #[serde(default)]
+    pub step: u32,
+    //extra seconds on top of the step
+    #[serde(default)]
+    pub delay: f32,
+    #[serde(default)]
+    pub hit_box: Option<[f32; 2]>,
+    #[serde(default)]
+    pub damage: Option<f32>,
+    #[serde(default)]
+    pub knockback: Option<f32>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct BossAttackWeight {
+    pub attack: BossAttack,
+    pub weight: f32,
+}
+
+fn default_boss_attacks() -> Vec<BossAttackWeight> {
+    return vec![
+        BossAttackWeight {
+            attack: BossAttack::Pattern("stomp".to_string()),
+            weight: 1.0,
+        },
+        BossAttackWeight {
+            attack: BossAttack::Hit,
+            weight: 1.0,
+        },
+    ];
+}
+
+//everything left out stays as it was in the phase before
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct BossPhaseInfo {
+    //starts once the health drops to this part of the max health
+    pub health: f32,
+    #[serde(default)]
+    pub walk_time: Option<f32>,
+    #[serde(default)]
+    pub stomp_delay_delta: Option<f32>,
+    #[serde(default)]
+    pub attacks: Option<Vec<BossAttackWeight>>,
+}
+#[derive(serde::Deserialize)]
+pub struct EnemyArchetype {
+    pub points: i32,
+    pub health: f32,
+    pub speed: f32,
+    pub scale: f32,
+    pub knockback_multiplier: f32,
+    //skeletons closer than this get attacked instead of the sword, 0 only goes for the sword
+    #[serde(default)]
+    pub aggro_radius: f32,
+    //seconds after a hit in which further hits only do immune_damage_factor of their damage
+    #[serde(default)]
+    pub immunity_time: f32,
+    #[serde(default)]
+    pub immune_damage_factor: f32,
+    pub attack_windup: f32,
+    pub attack_recover: f32,
+    pub hitter: Option<HitterInfo>,
+    pub behavior: EnemyBehavior,
+    pub sprite: EnemySpriteInfo,
+    pub animations: Vec<AnimationInfo>,
+    pub shadow_scale: [f32; 3],
+    pub corpse_scale: f32,
+    pub health_bar: Option<String>,
+    #[serde(default)]
+    pub skeleton: SkeletonInfo,
+}
+//what a corpse of the archetype turns into when raised
+//speed, damage and knockback scale the upgraded skeleton stats
+#[derive(serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct SkeletonInfo {
+    pub health: f32,
+    pub speed: f32,
+    pub damage: f32,
+    pub knockback: f32,
+    pub scale: f32,
+}
+impl Default for SkeletonInfo {
+    fn default() -> Self {
+        return SkeletonInfo {
+            health: 1.0,
+            speed: 1.0,
+            damage: 1.0,
+            knockback: 1.0,
+            scale: 1.0,
+        };
+    }
+}
+#[derive(serde::Deserialize, bevy::asset::Asset, bevy::reflect::TypePath)]
+pub struct EnemyInfos {
+    pub archetypes: HashMap<String, EnemyArchetype>,
 }
 
 #[derive(serde::Deserialize, bevy::asset::Asset, bevy::reflect::TypePath)]
@@ -201,3 +439,45 @@ pub struct UIAssets {
     #[asset(path = "gradient.png")]
     pub gradient: Handle<Image>,
 }
+
+#[derive(serde::Deserialize)]
+pub struct SpawnerInfo {
+    pub position: [f32; 3],
+    pub once: bool,
+    pub inactive: f32,
+    pub preheat: f32,
+    pub min: f32,
+    pub max: f32,
+    pub factor: f32,
+    pub interval: f32,
+    pub enemy_type: EnemyType,
+}
+#[derive(serde::Deserialize)]
+pub struct GroundInfo {
+    pub position: [f32; 3],
+    pub size: [f32; 2],
+}
+#[derive(serde::Deserialize)]
+pub struct DeceasedInfo {
+    pub position: [f32; 3],
+    pub enemy_type: EnemyType,
+}
+#[derive(serde::Deserialize, bevy::asset::Asset, bevy::reflect::TypePath)]
+pub struct LevelInfo {
+    pub name: String,
+    pub music: String,
+    pub ground: GroundInfo,
+    pub deceased: Vec<DeceasedInfo>,
+    pub spawners: Vec<SpawnerInfo>,
+}
+//every level file in the folder, sorted by path once loaded so indices stay the same between runs
+#[derive(AssetCollection, Resource)]
+pub struct LevelAssets {
+    #[cfg_attr(not(target_arch = "wasm32"), asset(path = "levels", collection(typed)))]
+    //the web build can't list folders, levels for it still have to be named here
+    #[cfg_attr(
+        target_arch = "wasm32",
+        asset(paths("levels/grumpy_sword.level.json"), collection(typed))
+    )]
+    pub levels: Vec<Handle<LevelInfo>>,
+}
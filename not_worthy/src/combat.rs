@@ -1,26 +1,28 @@
-use crate::asset_load::{EnemySounds, GameData, GameInfos, PlayerSounds};
+use crate::asset_load::{level_value, EnemySounds, GameData, GameInfos, PlayerSounds};
 use crate::effects::{AriseEffect, AttackEffect};
+use crate::game_rng::GameRng;
 use crate::game_state::{GameState, PauseState};
-use crate::input_manager::{Action, BasicControl};
+use crate::input_manager::GameplayInput;
 use crate::level_loading::SceneObject;
 use crate::movement::{Controllable, GameLayer};
 use crate::player_states::AttackNow;
-use crate::state_handling::get_sotred_value;
+use crate::skeleton_ai::{RightGroup, SquadOrders};
+use crate::spawning::{Enemy, EnemyType};
+use crate::state_handling::{load_save_data, Upgrade};
 use avian2d::prelude::{Collider, LayerMask, LinearVelocity, SpatialQuery, SpatialQueryFilter};
-use bevy::app::{App, Plugin, Update};
+use bevy::app::{App, FixedUpdate, Plugin};
 use bevy::asset::Assets;
 use bevy::audio::{AudioPlayer, PlaybackMode, PlaybackSettings};
-use bevy::math::{Quat, Vec2};
+use bevy::math::{Quat, Vec2, Vec3};
 use bevy::prelude::{
-    in_state, info, Commands, Component, Entity, Gamepad, GamepadAxis, GamepadButton,
-    IntoSystemConfigs, OnEnter, Query, Res, ResMut, Startup, SystemSet, Time, Timer, Transform,
-    Vec3Swizzles, With,
+    in_state, info, Commands, Component, Entity, Event, EventReader, EventWriter, Gamepad,
+    GamepadAxis, GamepadButton, Has, IntoSystemConfigs, OnEnter, Query, Res, ResMut, Startup,
+    SystemSet, Time, Timer, Transform, Vec3Swizzles, With, Without,
 };
 use bevy::time::TimerMode;
 use bevy_firework::core::ParticleSpawnerData;
 use bevy_pkv::PkvStore;
-use leafwing_input_manager::prelude::ActionState;
-use std::collections::VecDeque;
+use rand::Rng;
 use std::time::Duration;
 
 pub struct CombatPlugin;
@@ -30,18 +32,24 @@ pub struct CombatSet;
 
 impl Plugin for CombatPlugin {
     fn build(&self, app: &mut App) {
+        app.add_event::<DamageDealt>();
+        app.add_event::<EntityKilled>();
+        app.add_event::<DamageTaken>();
         app.add_systems(
             OnEnter(GameState::InGame),
             setup_player_attacks.run_if(in_state(GameState::InGame)),
         );
         app.add_systems(
-            Update,
+            FixedUpdate,
             ((
                 player_hit.run_if(in_state(GameState::InGame)),
                 hit_system
                     .after(player_hit)
                     .run_if(in_state(GameState::InGame)),
-                enemy_take_damage.after(hit_system),
+                tick_immunity_system,
+                enemy_take_damage
+                    .after(hit_system)
+                    .after(tick_immunity_system),
             )
                 .in_set(CombatSet))
             .run_if(in_state(PauseState::Running)),
@@ -56,11 +64,16 @@ fn setup_player_attacks(
     mut game_datas: ResMut<Assets<GameInfos>>,
     mut pkv: ResMut<PkvStore>,
 ) {
-    let attack_cooldown_level = get_sotred_value(&mut pkv, "attack_cooldown");
+    let save_data = load_save_data(&mut pkv);
+    let attack_cooldown_level = save_data.level(Upgrade::AttackCooldown);
 
     let mut attack_cooldown = 0.0;
     if let Some(game_data) = game_datas.get(game_data.data.id()) {
-        attack_cooldown = game_data.attack_cooldown[attack_cooldown_level as usize];
+        attack_cooldown = level_value(
+            &game_data.attack_cooldown,
+            attack_cooldown_level,
+            attack_cooldown,
+        );
     }
     commands.spawn((
         SceneObject,
@@ -74,12 +87,19 @@ fn setup_player_attacks(
 #[derive(Component)]
 pub struct Hitter {
     pub knockback: f32,
-    pub damage: f32,
+    //every hit rolls its damage between these
+    pub min_damage: f32,
+    pub max_damage: f32,
+    //0..1, a crit multiplies the rolled damage
+    pub crit_chance: f32,
+    pub crit_multiplier: f32,
     pub hit_box: Vec2,
     pub offset: Vec2,
     pub hit_mask: u32,
     pub spatial_query_filter: SpatialQueryFilter,
     pub single: bool,
+    //pushes away from the center of the hit box instead of the way the hitter faces
+    pub radial: bool,
 }
 #[derive(Component)]
 pub struct Direction {
@@ -101,14 +121,44 @@ impl Health {
     }
 }
 
-pub struct Attack {
-    damage: f32,
-    knockback: f32,
+//sent for every hit that lands, enemy_take_damage applies it
+#[derive(Event)]
+pub struct DamageDealt {
+    pub attacker: Entity,
+    pub victim: Entity,
+    pub amount: f32,
+    pub critical: bool,
+    pub knockback: f32,
+    //horizontal direction the victim gets pushed, away from the attacker
+    pub direction: f32,
+}
+
+//sent by enemy_take_damage with the damage that was actually applied
+#[derive(Event)]
+pub struct DamageTaken {
+    pub victim: Entity,
+    pub amount: f32,
+    pub critical: bool,
+    //false when a hero got hit
+    pub skeleton: bool,
+    pub position: Vec3,
+}
+
+//sent once when something dies, at the same time Dead is inserted
+#[derive(Event)]
+pub struct EntityKilled {
+    pub entity: Entity,
+    pub cause: Cause,
+    //None for skeletons
+    pub enemy_type: Option<EnemyType>,
+    pub points: i32,
+    pub position: Vec3,
 }
 
 #[derive(Component)]
 pub struct Stunned {}
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Cause {
     Out,
     Attack,
@@ -127,8 +177,34 @@ impl Default for Cause {
 #[derive(Component)]
 pub struct Opfer {
     pub hit_layer: u32,
-    pub hits: VecDeque<Attack>,
     pub knockback_multiplier: f32,
+    //runs after every full hit, hits before it is over only do immune_damage_factor of their damage
+    pub immunity: Timer,
+    //0 ignores hits during the immunity window completely
+    pub immune_damage_factor: f32,
+}
+
+impl Opfer {
+    pub fn new(
+        hit_layer: u32,
+        knockback_multiplier: f32,
+        immunity_time: f32,
+        immune_damage_factor: f32,
+    ) -> Self {
+        //starts out finished, so the first hit always lands fully
+        let mut immunity = Timer::from_seconds(immunity_time, TimerMode::Once);
+        immunity.tick(Duration::from_secs_f32(immunity_time));
+        return Opfer {
+            hit_layer,
+            knockback_multiplier,
+            immunity,
+            immune_damage_factor,
+        };
+    }
+
+    pub fn is_immune(&self) -> bool {
+        return !self.immunity.finished();
+    }
 }
 
 #[derive(Component)]
@@ -142,23 +218,32 @@ pub struct PlayerHit {}
 fn player_hit(
     time: Res<Time>,
     mut commands: Commands,
-    input_query: Query<(&ActionState<Action>), With<BasicControl>>,
+    input_query: Query<(&GameplayInput)>,
     mut player_setup_query: Query<(&mut PlayerCombatSettings), With<PlayerHit>>,
-    mut query: Query<(&Transform, &mut Direction, Entity), (With<Hitter>, With<Controllable>)>,
-    sound_asset: Res<PlayerSounds>,
+    mut query: Query<(&mut Direction, Has<RightGroup>, Entity), (With<Hitter>, With<Controllable>)>,
+    sound_asset: Option<Res<PlayerSounds>>,
     mut arise_effect_query: Query<(&mut ParticleSpawnerData), With<AttackEffect>>,
+    orders: Option<Res<SquadOrders>>,
 ) {
+    //autonomous skeletons attack on their own, the button is a rally order then
+    if (orders.as_ref().map_or(false, |orders| orders.autonomous)) {
+        return;
+    }
     let mut dirr = 0.0;
+    let mut right_dirr = 0.0;
     let mut attack = false;
-    for (action) in &input_query {
-        if action.just_pressed(&Action::Punch) {
+    for (input) in &input_query {
+        if input.punch {
             attack = true;
         }
 
-        let x = action.clamped_value(&Action::Move);
+        let x = input.movement;
         if x.abs() > 0.5 {
             dirr = x.signum();
         }
+        if input.right_movement.abs() > 0.5 {
+            right_dirr = input.right_movement.signum();
+        }
     }
 
     let Ok((mut player_combat_settings)) = player_setup_query.get_single_mut() else {
@@ -173,19 +258,26 @@ fn player_hit(
         attack = false;
     }
     if (attack) {
-        commands.spawn((
-            AudioPlayer::new(sound_asset.swoosh.clone()),
-            PlaybackSettings {
-                mode: PlaybackMode::Despawn,
-                ..Default::default()
-            },
-            SceneObject,
-        ));
+        if let Some(sound_asset) = &sound_asset {
+            commands.spawn((
+                AudioPlayer::new(sound_asset.swoosh.clone()),
+                PlaybackSettings {
+                    mode: PlaybackMode::Despawn,
+                    ..Default::default()
+                },
+                SceneObject,
+            ));
+        }
     }
-    if (dirr.abs() > 0.0 || attack) {
-        for (transform, mut direction, entity) in query.iter_mut() {
-            if (dirr.abs() > 0.0) {
-                direction.direction = dirr;
+    if (dirr.abs() > 0.0 || right_dirr.abs() > 0.0 || attack) {
+        for (mut direction, right_group, entity) in query.iter_mut() {
+            //split squads face the way their own group is steered
+            let group_dirr = match &orders {
+                Some(orders) => orders.group_movement(dirr, right_dirr, right_group),
+                None => dirr,
+            };
+            if (group_dirr.abs() > 0.0) {
+                direction.direction = group_dirr;
             }
             if (attack) {
                 for (mut effect) in arise_effect_query.iter_mut() {
@@ -231,13 +323,18 @@ pub struct Hitting {}
 fn hit_system(
     mut commands: Commands,
     query: Query<(&Transform, &Direction, &Hitter, Entity), With<Hitting>>,
-    mut opfer_query: Query<(&mut Opfer)>,
+    opfer_query: Query<(&Opfer, &Transform)>,
     spatial_query: SpatialQuery,
+    mut damage_events: EventWriter<DamageDealt>,
+    mut game_rng: ResMut<GameRng>,
 ) {
     for (transform, direction, hitter, entity) in query.iter() {
         hit(
             &spatial_query,
-            &mut opfer_query,
+            &opfer_query,
+            &mut damage_events,
+            &mut game_rng,
+            entity,
             &hitter,
             &direction,
             transform.translation.xy(),
@@ -250,76 +347,156 @@ fn hit_system(
 
 fn hit(
     spatial_query: &SpatialQuery,
-    mut opfer_query: &mut Query<&mut Opfer>,
+    opfer_query: &Query<(&Opfer, &Transform)>,
+    damage_events: &mut EventWriter<DamageDealt>,
+    game_rng: &mut GameRng,
+    attacker: Entity,
     hitter: &Hitter,
     direction: &Direction,
     origin: Vec2,
     spatial_query_filter: &SpatialQueryFilter,
 ) {
+    let center = origin + hitter.offset * direction.direction;
     let intersections = spatial_query.shape_intersections(
         &Collider::rectangle(hitter.hit_box.x, hitter.hit_box.y),
-        origin + hitter.offset * direction.direction,
+        center,
         0.0,
         &spatial_query_filter,
     );
-    let mut count = 0;
+    //every victim takes the full hit, the immunity window on Opfer keeps crowds from stacking damage
     for entity in intersections.iter() {
-        if (spatial_query_filter.mask.0 & 2 > 0) {
-            println!("ouch");
-        } // let opfer = opfer_query.get(*entity);
-        if let Ok(opfer) = opfer_query.get(*entity) {
+        if let Ok((opfer, victim_transform)) = opfer_query.get(*entity) {
             if ((1 << opfer.hit_layer & hitter.hit_mask) != 0) {
-                count += 1;
+                let offset = victim_transform.translation.x - center.x;
+                let push_direction = if (hitter.radial && offset != 0.0) {
+                    offset.signum()
+                } else {
+                    direction.direction
+                };
+                let (amount, critical) = roll_damage(game_rng, hitter);
+                damage_events.send(DamageDealt {
+                    attacker,
+                    victim: *entity,
+                    amount,
+                    critical,
+                    knockback: hitter.knockback,
+                    direction: push_direction,
+                });
                 if (hitter.single) {
                     break;
                 }
             }
         }
     }
+}
 
-    if (spatial_query_filter.mask.0 & 2 > 0) {
-        println!("getting hit {}", count);
+//rolled per victim, so one swing can crit some of them and not others
+fn roll_damage(game_rng: &mut GameRng, hitter: &Hitter) -> (f32, bool) {
+    let mut amount = hitter.min_damage;
+    if (hitter.max_damage > hitter.min_damage) {
+        amount = game_rng
+            .rng
+            .gen_range(hitter.min_damage..=hitter.max_damage);
     }
-    for entity in intersections.iter() {
-        if let Ok(mut opfer) = opfer_query.get_mut(*entity) {
-            if ((1 << opfer.hit_layer & hitter.hit_mask) != 0) {
-                opfer.hits.push_back(Attack {
-                    damage: hitter.damage / count as f32,
-                    knockback: hitter.knockback / count as f32,
-                });
-                if (hitter.single) {
-                    break;
-                }
-            }
-        }
+    let critical = hitter.crit_chance > 0.0 && game_rng.rng.gen::<f32>() < hitter.crit_chance;
+    if (critical) {
+        amount *= hitter.crit_multiplier;
+    }
+    return (amount, critical);
+}
+
+fn tick_immunity_system(time: Res<Time>, mut opfer_query: Query<&mut Opfer>) {
+    for mut opfer in opfer_query.iter_mut() {
+        opfer.immunity.tick(time.delta());
     }
 }
 
 fn enemy_take_damage(
     mut commands: Commands,
-    mut opfer_query: Query<(
-        Entity,
-        &mut LinearVelocity,
-        &mut Opfer,
-        &Transform,
-        &mut Health,
-    )>,
+    mut damage_events: EventReader<DamageDealt>,
+    mut killed_events: EventWriter<EntityKilled>,
+    mut taken_events: EventWriter<DamageTaken>,
+    mut opfer_query: Query<
+        (
+            &mut LinearVelocity,
+            &mut Opfer,
+            &Transform,
+            &mut Health,
+            Option<&Enemy>,
+        ),
+        Without<Dead>,
+    >,
     stunned_query: Query<&Stunned>,
 ) {
-    for (entity, mut linear_velocity, mut opfer, transform, mut health) in opfer_query.iter_mut() {
-        while let Some(element) = opfer.hits.pop_front() {
-            linear_velocity.0 += Vec2::new(
-                transform.translation.x.signum() * element.knockback * opfer.knockback_multiplier,
-                element.knockback * opfer.knockback_multiplier.abs(),
-            );
-            if stunned_query.get(entity).is_err() {
-                commands.entity(entity).insert(Stunned {});
+    for damage in damage_events.read() {
+        let Ok((mut linear_velocity, mut opfer, transform, mut health, enemy)) =
+            opfer_query.get_mut(damage.victim)
+        else {
+            continue;
+        };
+        //already killed by an earlier hit this tick
+        if (health.health <= 0.0) {
+            continue;
+        }
+        //hits during the immunity window are reduced and don't stun or restart the window
+        let factor = if (opfer.is_immune()) {
+            opfer.immune_damage_factor
+        } else {
+            1.0
+        };
+        if (factor <= 0.0) {
+            continue;
+        }
+        linear_velocity.0 += Vec2::new(
+            damage.direction * damage.knockback * opfer.knockback_multiplier * factor,
+            damage.knockback * opfer.knockback_multiplier * factor,
+        );
+        if (!opfer.is_immune()) {
+            if stunned_query.get(damage.victim).is_err() {
+                commands.entity(damage.victim).insert(Stunned {});
             }
-            health.health -= element.damage;
-            if (health.health <= 0.0) {
-                linear_velocity.0 = Vec2::ZERO;
-                commands.entity(entity).insert(Dead::default());
+            //a zero window would make the rest of this tick's hits count as immune
+            if (!opfer.immunity.duration().is_zero()) {
+                opfer.immunity.reset();
             }
         }
+        health.health -= damage.amount * factor;
+        taken_events.send(DamageTaken {
+            victim: damage.victim,
+            amount: damage.amount * factor,
+            critical: damage.critical,
+            skeleton: enemy.is_none(),
+            position: transform.translation,
+        });
+        if (health.health <= 0.0) {
+            linear_velocity.0 = Vec2::ZERO;
+            kill(
+                &mut commands,
+                &mut killed_events,
+                damage.victim,
+                Cause::Attack,
+                enemy,
+                transform,
+            );
+        }
     }
 }
+
+//the only way things should die, so EntityKilled is never missed
+pub fn kill(
+    commands: &mut Commands,
+    killed_events: &mut EventWriter<EntityKilled>,
+    entity: Entity,
+    cause: Cause,
+    enemy: Option<&Enemy>,
+    transform: &Transform,
+) {
+    commands.entity(entity).insert(Dead { reason: cause });
+    killed_events.send(EntityKilled {
+        entity,
+        cause,
+        enemy_type: enemy.map(|enemy| enemy.enemy_type.clone()),
+        points: enemy.map_or(0, |enemy| enemy.points),
+        position: transform.translation,
+    });
+}
@@ -0,0 +1,178 @@
+use crate::combat::DamageTaken;
+use crate::game_state::GameState;
+use crate::level_loading::{MainCamera, SceneObject};
+use crate::state_handling::Settings;
+use bevy::app::{App, Plugin, Update};
+use bevy::color::{Alpha, Color};
+use bevy::prelude::{
+    default, in_state, Camera, Commands, Component, Entity, EventReader, GlobalTransform,
+    IntoSystemConfigs, Node, OnEnter, PositionType, Query, Res, Text, TextColor, TextFont, Time,
+    Timer, Val, Vec3, Visibility, With, ZIndex,
+};
+use bevy::time::TimerMode;
+
+//numbers are recycled, a big fight never spawns more than this
+const POOL_SIZE: usize = 48;
+const LIFETIME: f32 = 0.8;
+//world units the number rises over its lifetime
+const RISE: f32 = 1.0;
+const HEIGHT: f32 = 0.8;
+const FONT_SIZE: f32 = 22.0;
+const CRIT_FONT_SIZE: f32 = 30.0;
+
+const SKELETON_HIT_COLOR: Color = Color::srgb(0.9, 0.25, 0.25);
+const HERO_HIT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const CRIT_COLOR: Color = Color::srgb(1.0, 0.78, 0.2);
+
+pub struct DamageNumberPlugin;
+
+impl Plugin for DamageNumberPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::InGame), spawn_damage_number_pool);
+        app.add_systems(
+            Update,
+            (
+                show_damage_number_system.run_if(damage_numbers_enabled),
+                animate_damage_number_system,
+            )
+                .chain()
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct DamageNumber {
+    timer: Timer,
+    //where the victim was when it got hit
+    position: Vec3,
+    color: Color,
+    active: bool,
+}
+
+fn damage_numbers_enabled(settings: Option<Res<Settings>>) -> bool {
+    return settings.map_or(true, |settings| settings.damage_numbers);
+}
+
+fn spawn_damage_number_pool(mut commands: Commands) {
+    for _ in 0..POOL_SIZE {
+        commands.spawn((
+            SceneObject,
+            DamageNumber {
+                timer: Timer::from_seconds(LIFETIME, TimerMode::Once),
+                position: Vec3::ZERO,
+                color: HERO_HIT_COLOR,
+                active: false,
+            },
+            Text::new(""),
+            TextFont {
+                font_size: FONT_SIZE,
+                ..default()
+            },
+            TextColor(HERO_HIT_COLOR),
+            Node {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            ZIndex(2),
+            Visibility::Hidden,
+        ));
+    }
+}
+
+fn format_damage(amount: f32) -> String {
+    if (amount >= 10.0) {
+        return format!("{:.0}", amount);
+    }
+    return format!("{:.1}", amount);
+}
+
+fn show_damage_number_system(
+    mut taken_events: EventReader<DamageTaken>,
+    mut number_query: Query<(
+        &mut DamageNumber,
+        &mut Text,
+        &mut TextFont,
+        &mut Visibility,
+        Entity,
+    )>,
+) {
+    for taken in taken_events.read() {
+        //a free number, or the one that has been up the longest
+        let mut slot = None;
+        let mut oldest = -1.0;
+        for (number, _, _, _, entity) in number_query.iter() {
+            let age = if number.active {
+                number.timer.elapsed_secs()
+            } else {
+                LIFETIME + 1.0
+            };
+            if (age > oldest) {
+                oldest = age;
+                slot = Some(entity);
+            }
+        }
+        let Some(slot) = slot else {
+            return;
+        };
+        let Ok((mut number, mut text, mut font, mut visibility, _)) = number_query.get_mut(slot)
+        else {
+            continue;
+        };
+
+        number.timer.reset();
+        number.position = taken.position;
+        number.active = true;
+        number.color = if taken.critical {
+            CRIT_COLOR
+        } else if taken.skeleton {
+            SKELETON_HIT_COLOR
+        } else {
+            HERO_HIT_COLOR
+        };
+        text.0 = format_damage(taken.amount);
+        if (taken.critical) {
+            text.0.push('!');
+            font.font_size = CRIT_FONT_SIZE;
+        } else {
+            font.font_size = FONT_SIZE;
+        }
+        *visibility = Visibility::Inherited;
+    }
+}
+
+fn animate_damage_number_system(
+    time: Res<Time>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut number_query: Query<(
+        &mut DamageNumber,
+        &mut Node,
+        &mut TextColor,
+        &mut Visibility,
+    )>,
+) {
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    for (mut number, mut node, mut text_color, mut visibility) in number_query.iter_mut() {
+        if (!number.active) {
+            continue;
+        }
+        number.timer.tick(time.delta());
+        if (number.timer.finished()) {
+            number.active = false;
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        let fraction = number.timer.fraction();
+        let position = number.position + Vec3::Y * (HEIGHT + RISE * fraction);
+        let Ok(screen_position) = camera.world_to_viewport(camera_transform, position) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        node.left = Val::Px(screen_position.x);
+        node.top = Val::Px(screen_position.y);
+        text_color.0 = number.color.with_alpha(1.0 - fraction * fraction);
+    }
+}
@@ -1,11 +1,14 @@
+use crate::combat::DamageDealt;
+use crate::end_boss::BossPhaseChanged;
 use crate::game_state::GameState;
 use crate::level_loading::SceneObject;
 use bevy::app::{App, Plugin, Startup, Update};
 use bevy::prelude::{
-    default, in_state, Alpha, Commands, Component, LinearRgba, OnEnter, Transform, Vec3,
+    default, in_state, Alpha, Commands, Component, EventReader, IntoSystemConfigs, LinearRgba,
+    Local, OnEnter, Query, Transform, Vec3, With, Without,
 };
 use bevy_firework::bevy_utilitarian::prelude::{RandF32, RandValue, RandVec3};
-use bevy_firework::core::{BlendMode, ParticleSpawner};
+use bevy_firework::core::{BlendMode, ParticleSpawner, ParticleSpawnerData};
 use bevy_firework::curve::{FireworkCurve, FireworkGradient};
 use bevy_firework::emission_shape::EmissionShape;
 use bevy_firework::plugin::ParticleSystemPlugin;
@@ -14,10 +17,19 @@ use std::f32::consts::PI;
 pub struct EffectPlugin;
 
 pub const THE_GREEN: LinearRgba = LinearRgba::new(0.16, 0.74, 0.26, 1.0);
+pub const CRIT_GOLD: LinearRgba = LinearRgba::new(1.0, 0.78, 0.2, 1.0);
+pub const RAGE_RED: LinearRgba = LinearRgba::new(0.9, 0.12, 0.08, 1.0);
+
+//crit bursts that can play at the same time
+const CRIT_POOL_SIZE: usize = 8;
 
 impl Plugin for EffectPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(GameState::InGame), spawn_particles_system);
+        app.add_systems(
+            Update,
+            (crit_effect_system, boss_phase_effect_system).run_if(in_state(GameState::InGame)),
+        );
         // app.add_systems(Update, (player_hit));
     }
 }
@@ -30,6 +42,58 @@ pub struct AriseEffect;
 
 #[derive(Component)]
 pub struct AttackEffect;
+
+//burst on whatever got hit by a crit
+//the bursts are taken in turn, so a sweep that crits several heroes shows all of them
+#[derive(Component)]
+pub struct CritEffect {
+    slot: usize,
+}
+
+fn crit_effect_system(
+    mut damage_events: EventReader<DamageDealt>,
+    mut next_slot: Local<usize>,
+    victim_query: Query<&Transform, Without<CritEffect>>,
+    mut crit_effect_query: Query<(&CritEffect, &mut ParticleSpawnerData, &mut Transform)>,
+) {
+    for damage in damage_events.read() {
+        if (!damage.critical) {
+            continue;
+        }
+        let Ok(victim_transform) = victim_query.get(damage.victim) else {
+            continue;
+        };
+        let slot = *next_slot;
+        *next_slot = (slot + 1) % CRIT_POOL_SIZE;
+        for (crit_effect, mut effect, mut transform) in crit_effect_query.iter_mut() {
+            if (crit_effect.slot != slot) {
+                continue;
+            }
+            transform.translation.x = victim_transform.translation.x;
+            transform.translation.y = victim_transform.translation.y;
+            effect.enabled = true;
+        }
+    }
+}
+//burst around the boss when it enters a new phase
+#[derive(Component)]
+pub struct BossPhaseEffect;
+
+fn boss_phase_effect_system(
+    mut phase_events: EventReader<BossPhaseChanged>,
+    mut phase_effect_query: Query<
+        (&mut ParticleSpawnerData, &mut Transform),
+        With<BossPhaseEffect>,
+    >,
+) {
+    for phase in phase_events.read() {
+        for (mut effect, mut transform) in phase_effect_query.iter_mut() {
+            transform.translation.x = phase.position.x;
+            transform.translation.y = phase.position.y;
+            effect.enabled = true;
+        }
+    }
+}
 fn spawn_particles_system(mut commands: Commands) {
     commands.spawn((
         AriseCooldownEffect,
@@ -126,4 +190,65 @@ fn spawn_particles_system(mut commands: Commands) {
         },
         Transform::from_xyz(0., -0.25, -1.),
     ));
+    for slot in 0..CRIT_POOL_SIZE {
+        commands.spawn((
+            CritEffect { slot },
+            SceneObject,
+            ParticleSpawner {
+                one_shot: true,
+                rate: 60.0,
+                emission_shape: EmissionShape::Circle {
+                    normal: Vec3::Z,
+                    radius: 0.2,
+                },
+                lifetime: RandF32 { min: 0.3, max: 0.5 },
+                inherit_parent_velocity: false,
+                initial_velocity_radial: RandF32 { min: 4.0, max: 7.0 },
+                initial_scale: RandF32 {
+                    min: 0.03,
+                    max: 0.1,
+                },
+                scale_curve: FireworkCurve::uneven_samples(vec![(0., 3.0), (1., 0.0)]),
+                color: FireworkGradient::uneven_samples(vec![
+                    (0., CRIT_GOLD.clone().with_alpha(1.0)),
+                    (1., CRIT_GOLD.clone().with_alpha(0.0)),
+                ]),
+                blend_mode: BlendMode::Add,
+                linear_drag: 3.0,
+                acceleration: Vec3::Y * -4.,
+                pbr: false,
+                starts_enabled: false,
+                ..default()
+            },
+            Transform::from_xyz(0., 0., -0.9),
+        ));
+    }
+    commands.spawn((
+        BossPhaseEffect,
+        SceneObject,
+        ParticleSpawner {
+            one_shot: true,
+            rate: 300.0,
+            emission_shape: EmissionShape::Sphere { 0: 1.0 },
+            lifetime: RandF32 { min: 0.6, max: 1.2 },
+            inherit_parent_velocity: false,
+            initial_velocity_radial: RandF32 { min: 3.0, max: 8.0 },
+            initial_scale: RandF32 {
+                min: 0.05,
+                max: 0.15,
+            },
+            scale_curve: FireworkCurve::uneven_samples(vec![(0., 3.0), (1., 0.0)]),
+            color: FireworkGradient::uneven_samples(vec![
+                (0., RAGE_RED.clone().with_alpha(1.0)),
+                (1., RAGE_RED.clone().with_alpha(0.0)),
+            ]),
+            blend_mode: BlendMode::Add,
+            linear_drag: 2.0,
+            acceleration: Vec3::Y * 2.,
+            pbr: false,
+            starts_enabled: false,
+            ..default()
+        },
+        Transform::from_xyz(0., 0., -0.8),
+    ));
 }
@@ -1,23 +1,29 @@
 use crate::animation::AnimationManager;
-use crate::asset_load::{BossSprite, EnemySounds, UIAssets};
-use crate::combat::{CombatSet, Dead, Direction, Hitter, Hitting};
-use crate::enemy::{AttackingHit, FinishedAttack, HitComposer, Walking};
-use crate::game_manager::Scorer;
+use crate::asset_load::{
+    BossAttack, BossAttackWeight, BossChargeInfo, BossPatternInfo, BossPhaseInfo, BossSprite,
+    EnemySounds, UIAssets,
+};
+use crate::combat::{CombatSet, Dead, Direction, Health, Hitter, Hitting};
+use crate::enemy::{AttackingHit, FinishedAttack, HitComposer, Walking, SWORD_POSITION};
+use crate::game_rng::GameRng;
 use crate::game_state::{GameState, PauseState};
+use crate::headless::visuals_enabled;
 use crate::level_loading::SceneObject;
 use crate::movement::GameLayer;
 use crate::spawning::Enemy;
-use crate::summoning::DeceasedSpawnPoint;
 use avian2d::prelude::{LayerMask, LinearVelocity, SpatialQueryFilter};
-use bevy::app::{App, FixedUpdate, Plugin, PreUpdate, Update};
+use bevy::app::{App, FixedPreUpdate, FixedUpdate, Plugin, Update};
 use bevy::audio::{AudioPlayer, PlaybackMode, PlaybackSettings};
+use bevy::log::{info, warn};
 use bevy::prelude::{
-    default, in_state, AlphaMode, Changed, Commands, Component, DespawnRecursiveExt, Entity,
-    IntoSystemConfigs, Query, Res, SystemSet, TextureAtlas, Time, Transform, Vec2, Vec3, With,
+    default, in_state, AlphaMode, Changed, Commands, Component, DespawnRecursiveExt, Entity, Event,
+    EventWriter, Has, IntoSystemConfigs, Query, Res, ResMut, SystemSet, TextureAtlas, Time,
+    Transform, Vec2, Vec3, With, Without,
 };
 use bevy::time::{Timer, TimerMode};
 use bevy_sprite3d::{Sprite3d, Sprite3dBuilder, Sprite3dParams};
 use rand::Rng;
+use std::collections::HashMap;
 use std::time::Duration;
 
 pub struct BossPlugin;
@@ -28,22 +34,34 @@ impl Plugin for BossPlugin {
     fn build(&self, app: &mut App) {
         //delete has to come before everything else to avoid panics (could also use try_insert)
 
+        app.add_event::<BossPhaseChanged>();
         app.add_systems(
-            PreUpdate,
+            FixedPreUpdate,
             ((
                 (boss_walking_state_system, boss_attacking_state_system).in_set(BossBehaviorSet),
                 boss_dead_state_system.before(BossBehaviorSet),
+                boss_phase_system.before(BossBehaviorSet),
             )
                 .run_if(in_state(GameState::InGame)),)
                 .run_if(in_state(PauseState::Running)),
         );
 
         app.add_systems(
-            Update,
-            ((stomp_attack_system, do_the_stomp_system)
+            FixedUpdate,
+            ((
+                stomp_attack_system,
+                do_the_stomp_system,
+                (charge_attack_system, charge_sweep_system).chain(),
+            )
                 .run_if(in_state(GameState::InGame))
                 .run_if(in_state(PauseState::Running))),
         );
+        app.add_systems(
+            Update,
+            (stomp_visual_system, charge_lane_visual_system)
+                .run_if(in_state(GameState::InGame))
+                .run_if(visuals_enabled),
+        );
     }
 }
 
@@ -100,25 +118,125 @@ fn boss_walking_state_system(
         }
     }
 }
+
+//health thresholds that change how the boss fights
+#[derive(Component)]
+pub struct BossPhases {
+    //sorted from the highest health threshold down
+    phases: Vec<BossPhaseInfo>,
+    //index of the phase that starts next
+    next: usize,
+    attacks: Vec<BossAttackWeight>,
+}
+
+impl BossPhases {
+    pub fn new(attacks: Vec<BossAttackWeight>, mut phases: Vec<BossPhaseInfo>) -> Self {
+        phases.sort_by(|a, b| b.health.total_cmp(&a.health));
+        return BossPhases {
+            phases,
+            next: 0,
+            attacks,
+        };
+    }
+
+    //weighted pick from the current pool, the basic hit if the pool is empty
+    fn pick_attack(&self, game_rng: &mut GameRng) -> BossAttack {
+        let total: f32 = self
+            .attacks
+            .iter()
+            .map(|attack| attack.weight.max(0.0))
+            .sum();
+        if (total <= 0.0) {
+            return BossAttack::Hit;
+        }
+        let mut roll = game_rng.rng.gen_range(0.0..total);
+        for attack in self.attacks.iter() {
+            roll -= attack.weight.max(0.0);
+            if (roll < 0.0) {
+                return attack.attack.clone();
+            }
+        }
+        return self
+            .attacks
+            .last()
+            .map_or(BossAttack::Hit, |attack| attack.attack.clone());
+    }
+}
+
+//sent when the boss enters a new phase, phase counts from 1
+#[derive(Event)]
+pub struct BossPhaseChanged {
+    pub entity: Entity,
+    pub phase: usize,
+    pub position: Vec3,
+}
+
+fn boss_phase_system(
+    mut phase_query: Query<(
+        &mut BossPhases,
+        &Health,
+        &mut BossWalkStateComposer,
+        &mut StompComposer,
+        &Transform,
+        Entity,
+    )>,
+    mut phase_events: EventWriter<BossPhaseChanged>,
+) {
+    for (mut phases, health, mut walk_composer, mut stomp_composer, transform, entity) in
+        phase_query.iter_mut()
+    {
+        let fraction = health.health / health.max_health;
+        //a big hit can skip phases, all of them get applied in order
+        while let Some(phase) = phases.phases.get(phases.next).cloned() {
+            if (fraction > phase.health) {
+                break;
+            }
+            if let Some(walk_time) = phase.walk_time {
+                walk_composer
+                    .timer
+                    .set_duration(Duration::from_secs_f32(walk_time));
+            }
+            if let Some(delay_delta) = phase.stomp_delay_delta {
+                stomp_composer.delay_delta = delay_delta;
+            }
+            if let Some(attacks) = phase.attacks {
+                phases.attacks = attacks;
+            }
+            phases.next += 1;
+            info!("boss phase {}", phases.next);
+            phase_events.send(BossPhaseChanged {
+                entity,
+                phase: phases.next,
+                position: transform.translation,
+            });
+        }
+    }
+}
+
 fn boss_attacking_state_system(
     mut commands: Commands,
-    mut state_query: Query<(&mut BossAttackingState, Entity)>,
+    mut state_query: Query<(&mut BossAttackingState, &BossPhases, Entity)>,
     attack_finished_query: Query<(&FinishedAttack)>,
     dead_query: Query<(&Dead)>,
+    mut game_rng: ResMut<GameRng>,
 ) {
-    for (mut state, entity) in state_query.iter_mut() {
+    for (mut state, phases, entity) in state_query.iter_mut() {
         if (state.new) {
             state.new = false;
-            let attack = rand::thread_rng().gen_range(0..=1);
-            match attack {
-                0 => {
-                    commands.entity(entity).insert(AttackingStomp { new: true });
+            match phases.pick_attack(&mut game_rng) {
+                BossAttack::Pattern(pattern) => {
+                    commands
+                        .entity(entity)
+                        .insert(AttackingStomp { new: true, pattern });
                 }
-
-                1 => {
+                BossAttack::Hit => {
                     commands.entity(entity).insert(AttackingHit { new: true });
                 }
-                _ => {}
+                BossAttack::Charge => {
+                    commands
+                        .entity(entity)
+                        .insert(AttackingCharge { new: true });
+                }
             }
         }
         if let Ok(dead) = dead_query.get(entity) {
@@ -130,6 +248,7 @@ fn boss_attacking_state_system(
         if let Ok(finished_attack) = attack_finished_query.get(entity) {
             commands.entity(entity).remove::<FinishedAttack>();
             commands.entity(entity).remove::<AttackingStomp>();
+            commands.entity(entity).remove::<AttackingCharge>();
             commands
                 .entity(entity)
                 .insert(BossWalkingState { new: true });
@@ -137,25 +256,10 @@ fn boss_attacking_state_system(
         }
     }
 }
-fn boss_dead_state_system(
-    mut commands: Commands,
-    state_query: Query<(&BossDeadState, &Enemy, &Transform, Entity)>,
-    mut scorer_query: Query<&mut Scorer>,
-) {
-    let Ok(mut scorer) = scorer_query.get_single_mut() else {
-        return;
-    };
-    for (state, enemy, transform, entity) in state_query.iter() {
+fn boss_dead_state_system(mut commands: Commands, state_query: Query<(&BossDeadState, Entity)>) {
+    //score and corpse come from the EntityKilled event
+    for (state, entity) in state_query.iter() {
         commands.entity(entity).despawn_recursive();
-        scorer.incoming.push_back(enemy.points);
-        let enem_type = enemy.enemy_type.clone();
-        commands.spawn((
-            SceneObject {},
-            DeceasedSpawnPoint {
-                enemy_type: enem_type,
-            },
-            Transform::from_translation(transform.translation),
-        ));
     }
 }
 
@@ -163,14 +267,16 @@ fn boss_dead_state_system(
 pub struct StompComposer {
     pub(crate) timer: Timer,
     pub(crate) after_timer: Timer,
-    pub(crate) delay: f32,
     pub(crate) delay_delta: f32,
     pub(crate) state: usize,
+    pub(crate) patterns: HashMap<String, BossPatternInfo>,
 }
 
+//runs one of the boss patterns
 #[derive(Component)]
 pub struct AttackingStomp {
     new: bool,
+    pattern: String,
 }
 
 #[derive(Component)]
@@ -178,51 +284,36 @@ pub struct StompThing {
     display_delay: Timer,
     delay: Timer,
     delete_timer: Timer,
+    hit_box: Vec2,
+    damage: f32,
+    knockback: f32,
 }
 
 fn do_the_stomp_system(
     time: Res<Time>,
-    mut stomp_query: Query<(&mut StompThing, &Transform, Entity)>,
-    mut sprite_query: Query<&mut Sprite3d>,
+    mut stomp_query: Query<(&mut StompThing, Entity)>,
     mut commands: Commands,
-    attack_asset: Res<BossSprite>,
-    mut sprite_params: Sprite3dParams,
 ) {
-    for (mut stomp_thing, transform, entity) in stomp_query.iter_mut() {
-        let telegraphSprite = Sprite3dBuilder {
-            image: attack_asset.stomp_attack.clone(),
-            pixels_per_metre: 128.0,
-            alpha_mode: AlphaMode::Blend,
-            unlit: true,
-            ..default()
-        };
-        let texture_atlas = TextureAtlas {
-            layout: attack_asset.stomp_layout.clone(),
-            index: 0,
-        };
+    for (mut stomp_thing, entity) in stomp_query.iter_mut() {
         stomp_thing.delete_timer.tick(time.delta());
         stomp_thing.delay.tick(time.delta());
         stomp_thing.display_delay.tick(time.delta());
-        if (stomp_thing.display_delay.just_finished()) {
-            commands
-                .entity(entity)
-                .insert(telegraphSprite.bundle_with_atlas(&mut sprite_params, texture_atlas));
-        }
         if (stomp_thing.delay.just_finished()) {
-            if let Ok(mut sprite) = sprite_query.get_mut(entity) {
-                sprite.texture_atlas.as_mut().unwrap().index = 1;
-            }
             commands.entity(entity).insert((
                 Hitter {
-                    knockback: 0.0,
-                    damage: 100000.0,
-                    hit_box: Vec2::new(0.8, 10.0),
+                    knockback: stomp_thing.knockback,
+                    min_damage: stomp_thing.damage,
+                    max_damage: stomp_thing.damage,
+                    crit_chance: 0.0,
+                    crit_multiplier: 1.0,
+                    hit_box: stomp_thing.hit_box,
                     offset: Vec2::ZERO,
                     hit_mask: 2,
                     spatial_query_filter: SpatialQueryFilter::from_mask(LayerMask::from(
                         GameLayer::Player,
                     )),
                     single: false,
+                    radial: true,
                 },
                 Direction { direction: 1.0 },
                 Hitting {},
@@ -233,6 +324,43 @@ fn do_the_stomp_system(
         }
     }
 }
+
+//telegraph sprite of the stomp, skipped when running headless
+//the timers tick in FixedUpdate, so this looks at finished instead of just_finished
+fn stomp_visual_system(
+    stomp_query: Query<(&StompThing, Entity, Has<Sprite3d>)>,
+    mut sprite_query: Query<&mut Sprite3d>,
+    mut commands: Commands,
+    attack_asset: Res<BossSprite>,
+    mut sprite_params: Sprite3dParams,
+) {
+    for (stomp_thing, entity, has_sprite) in stomp_query.iter() {
+        if (stomp_thing.display_delay.finished() && !has_sprite) {
+            let telegraphSprite = Sprite3dBuilder {
+                image: attack_asset.stomp_attack.clone(),
+                pixels_per_metre: 128.0,
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            };
+            let texture_atlas = TextureAtlas {
+                layout: attack_asset.stomp_layout.clone(),
+                index: 0,
+            };
+            commands
+                .entity(entity)
+                .insert(telegraphSprite.bundle_with_atlas(&mut sprite_params, texture_atlas));
+        }
+        if (stomp_thing.delay.finished()) {
+            if let Ok(mut sprite) = sprite_query.get_mut(entity) {
+                let atlas = sprite.texture_atlas.as_mut().unwrap();
+                if (atlas.index != 1) {
+                    atlas.index = 1;
+                }
+            }
+        }
+    }
+}
 fn stomp_attack_system(
     time: Res<Time>,
     mut commands: Commands,
@@ -244,46 +372,71 @@ fn stomp_attack_system(
         Entity,
     )>,
     mut animation_query: Query<&mut AnimationManager>,
+    mut game_rng: ResMut<GameRng>,
 ) {
     for (mut stomp_composer, mut linear_velocity, mut attacking_stomp, transform, entity) in
         query.iter_mut()
     {
         if (attacking_stomp.new) {
+            attacking_stomp.new = false;
+            let Some(pattern) = stomp_composer
+                .patterns
+                .get(&attacking_stomp.pattern)
+                .cloned()
+            else {
+                warn!("boss has no pattern {}", attacking_stomp.pattern);
+                commands.entity(entity).insert(FinishedAttack {});
+                commands.entity(entity).remove::<AttackingStomp>();
+                continue;
+            };
             linear_velocity.x = 0.0;
+            stomp_composer
+                .timer
+                .set_duration(Duration::from_secs_f32(pattern.windup));
+            stomp_composer
+                .after_timer
+                .set_duration(Duration::from_secs_f32(pattern.recover));
             stomp_composer.timer.reset();
             stomp_composer.after_timer.reset();
             stomp_composer.state = 0;
-            attacking_stomp.new = false;
             if let Ok(mut anim) = animation_query.get_mut(entity) {
                 anim.running = 4;
                 anim.new = true;
             }
-            let offset = rand::thread_rng().gen_range(-1.5..1.5);
-            let dist_between: f32 = 3.0;
+            let mut offset = 0.0;
+            if (pattern.random_offset > 0.0) {
+                offset = game_rng
+                    .rng
+                    .gen_range(-pattern.random_offset..pattern.random_offset);
+            }
 
-            for i in (-4..=4) {
+            for strike in pattern.strikes.iter() {
+                let step_delay = strike.step as f32 * stomp_composer.delay_delta + strike.delay;
                 commands.spawn((
                     SceneObject,
                     StompThing {
                         display_delay: Timer::new(
-                            Duration::from_secs_f32((i as f32).abs() * stomp_composer.delay_delta),
+                            Duration::from_secs_f32(step_delay),
                             TimerMode::Once,
                         ),
                         delay: Timer::new(
-                            Duration::from_secs_f32(
-                                stomp_composer.delay
-                                    + (i as f32).abs() * stomp_composer.delay_delta,
-                            ),
+                            Duration::from_secs_f32(pattern.delay + step_delay),
+                            TimerMode::Once,
+                        ),
+                        delete_timer: Timer::new(
+                            Duration::from_secs_f32(pattern.lifetime),
                             TimerMode::Once,
                         ),
-                        delete_timer: Timer::new(Duration::from_secs_f32(1.5), TimerMode::Once),
+                        hit_box: Vec2::from(strike.hit_box.unwrap_or(pattern.hit_box)),
+                        damage: strike.damage.unwrap_or(pattern.damage),
+                        knockback: strike.knockback.unwrap_or(pattern.knockback),
                     },
                     Transform::from_translation(Vec3::new(
-                        transform.translation.x + offset + i as f32 * dist_between,
+                        transform.translation.x + offset + strike.position,
                         0.0,
                         0.5,
                     ))
-                    .with_scale(Vec3::splat(1.3)),
+                    .with_scale(Vec3::splat(pattern.scale)),
                 ));
             }
         }
@@ -312,3 +465,260 @@ fn stomp_attack_system(
     //for entities with Attacking
     //check if there are enems in attack state without
 }
+
+//how often the sweep hits while the boss dashes, a skeleton in the way gets hit again after this
+const CHARGE_HIT_INTERVAL: f32 = 0.1;
+//a dash held up by something gives up after this much longer than it should take
+const CHARGE_TIME_MARGIN: f32 = 1.5;
+
+#[derive(Component)]
+pub struct ChargeComposer {
+    pub(crate) windup: Timer,
+    pub(crate) recover: Timer,
+    pub(crate) dash: Timer,
+    pub(crate) charge: Option<BossChargeInfo>,
+    pub(crate) state: usize,
+    //where the dash started and which way it goes
+    pub(crate) start: f32,
+    pub(crate) direction: f32,
+}
+
+impl ChargeComposer {
+    pub fn new(charge: Option<BossChargeInfo>) -> Self {
+        let (windup, recover) = charge
+            .as_ref()
+            .map_or((0.0, 0.0), |charge| (charge.windup, charge.recover));
+        let dash = charge.as_ref().map_or(0.0, |charge| {
+            if (charge.speed > 0.0) {
+                charge.distance / charge.speed * CHARGE_TIME_MARGIN
+            } else {
+                0.0
+            }
+        });
+        return ChargeComposer {
+            windup: Timer::new(Duration::from_secs_f32(windup), TimerMode::Once),
+            recover: Timer::new(Duration::from_secs_f32(recover), TimerMode::Once),
+            dash: Timer::new(Duration::from_secs_f32(dash), TimerMode::Once),
+            charge,
+            state: 0,
+            start: 0.0,
+            direction: 1.0,
+        };
+    }
+}
+
+#[derive(Component)]
+pub struct AttackingCharge {
+    new: bool,
+}
+
+//hit box that follows the boss while it dashes
+#[derive(Component)]
+pub struct ChargeSweep {
+    boss: Entity,
+    interval: Timer,
+}
+
+//telegraph of the lane the boss is about to dash through
+#[derive(Component)]
+pub struct ChargeLane {
+    boss: Entity,
+}
+
+fn charge_attack_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(
+        &mut ChargeComposer,
+        &mut LinearVelocity,
+        &mut AttackingCharge,
+        &mut Direction,
+        &Transform,
+        Entity,
+    )>,
+    mut animation_query: Query<&mut AnimationManager>,
+) {
+    for (
+        mut composer,
+        mut linear_velocity,
+        mut attacking_charge,
+        mut direction,
+        transform,
+        entity,
+    ) in query.iter_mut()
+    {
+        let Some(charge) = composer.charge.clone() else {
+            warn!("boss has no charge to do");
+            commands.entity(entity).insert(FinishedAttack {});
+            commands.entity(entity).remove::<AttackingCharge>();
+            continue;
+        };
+        let x = transform.translation.x;
+        if (attacking_charge.new) {
+            attacking_charge.new = false;
+            linear_velocity.x = 0.0;
+            composer.windup.reset();
+            composer.recover.reset();
+            composer.dash.reset();
+            composer.state = 0;
+            composer.start = x;
+            //always through the skeletons guarding the sword
+            composer.direction = (SWORD_POSITION.x - x).signum();
+            if (composer.direction == 0.0) {
+                composer.direction = direction.direction;
+            }
+            direction.direction = composer.direction;
+            if let Ok(mut anim) = animation_query.get_mut(entity) {
+                anim.running = 4;
+                anim.new = true;
+            }
+            commands.spawn((
+                SceneObject,
+                ChargeLane { boss: entity },
+                Transform::from_translation(Vec3::new(
+                    x + composer.direction * charge.distance / 2.0,
+                    0.0,
+                    0.5,
+                ))
+                .with_scale(Vec3::new(charge.distance, 1.0, 1.0)),
+            ));
+        }
+        match composer.state {
+            0 => {
+                composer.windup.tick(time.delta());
+                if (composer.windup.just_finished()) {
+                    if let Ok(mut anim) = animation_query.get_mut(entity) {
+                        anim.running = 3;
+                        anim.new = true;
+                    }
+                    commands.spawn((
+                        SceneObject,
+                        ChargeSweep {
+                            boss: entity,
+                            interval: Timer::new(
+                                Duration::from_secs_f32(CHARGE_HIT_INTERVAL),
+                                TimerMode::Repeating,
+                            ),
+                        },
+                        Hitter {
+                            knockback: charge.knockback,
+                            min_damage: charge.damage,
+                            max_damage: charge.damage,
+                            crit_chance: 0.0,
+                            crit_multiplier: 1.0,
+                            hit_box: Vec2::from(charge.hit_box),
+                            offset: Vec2::ZERO,
+                            hit_mask: 2,
+                            spatial_query_filter: SpatialQueryFilter::from_mask(LayerMask::from(
+                                GameLayer::Player,
+                            )),
+                            single: false,
+                            radial: false,
+                        },
+                        Direction {
+                            direction: composer.direction,
+                        },
+                        Transform::from_translation(transform.translation),
+                        Hitting {},
+                    ));
+                    composer.state = 1;
+                }
+            }
+            1 => {
+                linear_velocity.x = composer.direction * charge.speed;
+                composer.dash.tick(time.delta());
+                //skeletons or a wall can keep the boss from getting the whole way
+                if ((x - composer.start).abs() >= charge.distance || composer.dash.finished()) {
+                    linear_velocity.x = 0.0;
+                    if let Ok(mut anim) = animation_query.get_mut(entity) {
+                        anim.running = 0;
+                        anim.new = true;
+                    }
+                    composer.state = 2;
+                }
+            }
+            2 => {
+                composer.recover.tick(time.delta());
+                if (composer.recover.just_finished()) {
+                    commands.entity(entity).insert(FinishedAttack {});
+                    commands.entity(entity).remove::<AttackingCharge>();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+//sweep and lane only live as long as the dash they belong to
+fn charge_sweep_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    boss_query: Query<(&Transform, &ChargeComposer), With<AttackingCharge>>,
+    mut sweep_query: Query<(&mut ChargeSweep, &mut Transform, Entity), Without<ChargeComposer>>,
+    lane_query: Query<(&ChargeLane, Entity)>,
+) {
+    for (mut sweep, mut transform, entity) in sweep_query.iter_mut() {
+        let Ok((boss_transform, composer)) = boss_query.get(sweep.boss) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        if (composer.state != 1) {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        transform.translation = boss_transform.translation;
+        sweep.interval.tick(time.delta());
+        if (sweep.interval.just_finished()) {
+            commands.entity(entity).insert(Hitting {});
+        }
+    }
+    for (lane, entity) in lane_query.iter() {
+        let dashing = boss_query
+            .get(lane.boss)
+            .map_or(false, |(_, composer)| composer.state < 2);
+        if (!dashing) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+//same marker as the stomp, stretched over the lane
+fn charge_lane_visual_system(
+    lane_query: Query<(&ChargeLane, Entity, Has<Sprite3d>)>,
+    boss_query: Query<&ChargeComposer>,
+    mut sprite_query: Query<&mut Sprite3d>,
+    mut commands: Commands,
+    attack_asset: Res<BossSprite>,
+    mut sprite_params: Sprite3dParams,
+) {
+    for (lane, entity, has_sprite) in lane_query.iter() {
+        if (!has_sprite) {
+            let telegraph_sprite = Sprite3dBuilder {
+                image: attack_asset.stomp_attack.clone(),
+                pixels_per_metre: 128.0,
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            };
+            let texture_atlas = TextureAtlas {
+                layout: attack_asset.stomp_layout.clone(),
+                index: 0,
+            };
+            commands
+                .entity(entity)
+                .insert(telegraph_sprite.bundle_with_atlas(&mut sprite_params, texture_atlas));
+            continue;
+        }
+        let dashing = boss_query
+            .get(lane.boss)
+            .map_or(false, |composer| composer.state == 1);
+        if (dashing) {
+            if let Ok(mut sprite) = sprite_query.get_mut(entity) {
+                let atlas = sprite.texture_atlas.as_mut().unwrap();
+                if (atlas.index != 1) {
+                    atlas.index = 1;
+                }
+            }
+        }
+    }
+}
@@ -1,21 +1,20 @@
 use crate::animation::AnimationManager;
 use crate::asset_load::{EnemySounds, EnemySprite};
 use crate::combat::{hit_test, CombatSet, Dead, Direction, Hitter, Hitting, Opfer, Stunned};
-use crate::game_manager::Scorer;
 use crate::game_state::{GameState, PauseState};
-use crate::hit_detection::{test_point, HitDetection};
+use crate::hit_detection::{Faction, HitDetection};
 use crate::level_loading::SceneObject;
 use crate::movement::GameLayer;
 use crate::spawning::{Enemy, EnemyType, TimeTravel};
-use crate::summoning::{spawn_deceased, DeceasedSpawnPoint};
+use crate::summoning::{spawn_deceased, Player};
 use avian2d::prelude::{LayerMask, LinearVelocity, SpatialQuery, SpatialQueryFilter};
-use bevy::app::{App, FixedUpdate, Plugin, PreUpdate, Update};
+use bevy::app::{App, FixedPreUpdate, FixedUpdate, Plugin};
 use bevy::audio::{AudioPlayer, PlaybackMode, PlaybackSettings};
 use bevy::hierarchy::DespawnRecursiveExt;
 use bevy::log::tracing_subscriber::fmt::time;
 use bevy::prelude::{
     in_state, Bundle, Commands, Component, Entity, IntoSystemConfigs, Query, Res, SystemSet, Time,
-    TimerMode, Transform, Vec2, Vec3Swizzles, With,
+    TimerMode, Transform, Vec2, Vec3Swizzles, With, Without,
 };
 use bevy::time::Timer;
 use bevy_sprite3d::Sprite3dParams;
@@ -29,8 +28,13 @@ pub struct EnemBehaviorSet;
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
-            Update,
+            FixedUpdate,
             (
+                aggro_system
+                    .before(walk_to_target)
+                    .before(walk_to_target_time_travel)
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(in_state(PauseState::Running)),
                 walk_to_target
                     .before(CombatSet)
                     .run_if(in_state(GameState::InGame))
@@ -49,7 +53,7 @@ impl Plugin for EnemyPlugin {
         //delete has to come before everything else to avoid panics (could also use try_insert)
 
         app.add_systems(
-            PreUpdate,
+            FixedPreUpdate,
             ((
                 (
                     basic_enem_active_state_system,
@@ -71,6 +75,61 @@ pub struct Target {
     pub pos: Vec2,
 }
 
+//where everyone walks when there is no skeleton around
+pub const SWORD_POSITION: Vec2 = Vec2::ZERO;
+//how often an enemy looks for the nearest skeleton again
+const RETARGET_TIME: f32 = 0.5;
+
+//goes for skeletons within the radius instead of the sword
+#[derive(Component)]
+pub struct Aggro {
+    pub radius: f32,
+    pub retarget: Timer,
+    pub skeleton: Option<Entity>,
+}
+
+impl Aggro {
+    pub fn new(radius: f32) -> Self {
+        //finished right away, so the first target is picked on spawn
+        let mut retarget = Timer::from_seconds(RETARGET_TIME, TimerMode::Repeating);
+        retarget.set_elapsed(Duration::from_secs_f32(RETARGET_TIME));
+        return Aggro {
+            radius,
+            retarget,
+            skeleton: None,
+        };
+    }
+}
+
+fn aggro_system(
+    time: Res<Time>,
+    hit_detection: Res<HitDetection>,
+    mut query: Query<(&mut Aggro, &mut Target, &Transform), Without<Player>>,
+    skeleton_query: Query<&Transform, (With<Player>, Without<Dead>)>,
+) {
+    for (mut aggro, mut target, transform) in query.iter_mut() {
+        aggro.retarget.tick(time.delta());
+        if (aggro.retarget.just_finished()) {
+            aggro.skeleton = None;
+            //no need to aggro on the sword, heroes walk to it anyway
+            if (aggro.radius > 0.0) {
+                aggro.skeleton = hit_detection
+                    .nearest(Faction::Skeleton, transform.translation.x, aggro.radius)
+                    .map(|(entity, _)| entity);
+            }
+        }
+
+        //follow the skeleton between retargets, it might have died in the meantime
+        target.pos = match aggro
+            .skeleton
+            .and_then(|entity| skeleton_query.get(entity).ok())
+        {
+            Some(skeleton_transform) => skeleton_transform.translation.xy(),
+            None => SWORD_POSITION,
+        };
+    }
+}
+
 #[derive(Component)]
 pub struct Walking {}
 #[derive(Component)]
@@ -121,18 +180,15 @@ fn walk_to_target_time_travel(
 fn check_attack_system(
     mut commands: Commands,
     attack_check_query: Query<(&Hitter, &Direction, &Transform, Entity), With<AttackCheck>>,
-    hit_detection_query: Query<&HitDetection>, // spatial_query: SpatialQuery,
-                                               // opfer_query: Query<(&Opfer)>,
+    hit_detection: Res<HitDetection>, // spatial_query: SpatialQuery,
+                                      // opfer_query: Query<(&Opfer)>,
 ) {
-    if let Ok(hit_detection) = hit_detection_query.get_single() {
-        for (hitter, direction, transform, entity) in attack_check_query.iter() {
-            let hit: bool = test_point(
-                &hit_detection.space,
-                transform.translation.x + hitter.offset.x * direction.direction,
-            );
-            if (hit) {
-                commands.entity(entity).insert(AttackReady {});
-            }
+    for (hitter, direction, transform, entity) in attack_check_query.iter() {
+        let x = transform.translation.x + hitter.offset.x * direction.direction;
+        let hit: bool = hit_detection.test_point(Faction::Skeleton, x)
+            || hit_detection.test_point(Faction::Sword, x);
+        if (hit) {
+            commands.entity(entity).insert(AttackReady {});
         }
     }
 }
@@ -160,7 +216,7 @@ fn attack_system(
         Entity,
     )>,
     mut animation_query: Query<&mut AnimationManager>,
-    sound_asset: Res<EnemySounds>,
+    sound_asset: Option<Res<EnemySounds>>,
 ) {
     for (mut hit_composer, mut linear_velocity, mut attacking_hit, transform, entity) in
         query.iter_mut()
@@ -180,17 +236,19 @@ fn attack_system(
             0 => {
                 hit_composer.timer.tick(time.delta());
                 if (hit_composer.timer.just_finished()) {
-                    commands.spawn((
-                        AudioPlayer::new(sound_asset.swoosh.clone()),
-                        Transform::from_translation(transform.translation),
-                        PlaybackSettings {
-                            mode: PlaybackMode::Despawn,
-                            spatial: true,
-
-                            ..Default::default()
-                        },
-                        SceneObject,
-                    ));
+                    if let Some(sound_asset) = &sound_asset {
+                        commands.spawn((
+                            AudioPlayer::new(sound_asset.swoosh.clone()),
+                            Transform::from_translation(transform.translation),
+                            PlaybackSettings {
+                                mode: PlaybackMode::Despawn,
+                                spatial: true,
+
+                                ..Default::default()
+                            },
+                            SceneObject,
+                        ));
+                    }
                     commands.entity(entity).insert(Hitting {});
                     if let Ok(mut anim) = animation_query.get_mut(entity) {
                         anim.running = 2;
@@ -394,24 +452,11 @@ pub struct BacicEnemDeadState {
 }
 fn basic_enem_dead_state_system(
     mut commands: Commands,
-    mut dead_state_query: Query<(&BacicEnemDeadState, Entity, &Enemy, &Transform)>,
-    mut scorer_query: Query<&mut Scorer>,
+    mut dead_state_query: Query<(&BacicEnemDeadState, Entity)>,
 ) {
-    let Ok(mut scorer) = scorer_query.get_single_mut() else {
-        return;
-    };
-
-    for (mut state, entity, enemy, transform) in dead_state_query.iter() {
+    //score and corpse come from the EntityKilled event
+    for (mut state, entity) in dead_state_query.iter() {
         commands.entity(entity).despawn_recursive();
-        scorer.incoming.push_back(enemy.points);
-        let enem_type = enemy.enemy_type.clone();
-        commands.spawn((
-            SceneObject {},
-            DeceasedSpawnPoint {
-                enemy_type: enem_type,
-            },
-            Transform::from_translation(transform.translation),
-        ));
     }
 }
 
@@ -1,27 +1,45 @@
-use crate::game_state::GameState;
+use crate::asset_load::{level_value, GameData, GameInfos};
+use crate::combat::{CombatSet, Dead, EntityKilled, Health, Opfer};
+use crate::enemy::SWORD_POSITION;
+use crate::game_state::{GameState, PauseState};
+use crate::hit_detection::{Faction, HitDetection};
 use crate::level_loading::SceneObject;
-use crate::spawning::Enemy;
-use crate::state_handling::{get_sotred_value, store_value};
+use crate::movement::GameLayer;
+use crate::state_handling::{load_save_data, store_save_data, Upgrade};
+use crate::ui_stuff::HealthBarInitiator;
+use avian2d::prelude::{Collider, CollisionLayers, LayerMask, LinearVelocity, RigidBody};
 use bevy::app::{App, Plugin, Startup};
+use bevy::asset::Assets;
 use bevy::prelude::{
-    in_state, Commands, Component, Entity, IntoSystemConfigs, NextState, OnEnter, OnExit, Query,
-    ResMut, Transform, Update, With,
+    in_state, Commands, Component, Entity, EventReader, FixedUpdate, IntoSystemConfigs, NextState,
+    OnEnter, OnExit, Query, Res, ResMut, Time, Transform, Update, With, Without,
 };
 use bevy_pkv::PkvStore;
-use std::collections::VecDeque;
+
+//used until the game data is there
+const DEFAULT_SWORD_HEALTH: f32 = 10.0;
+//the sword only repairs itself while no hero is this close
+const REPAIR_CALM_RADIUS: f32 = 3.0;
 
 pub struct GameManagerPlugin;
 
 impl Plugin for GameManagerPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
-            Update,
-            (check_lose_system, handle_score_system).run_if(in_state(GameState::InGame)),
+            FixedUpdate,
+            (
+                check_lose_system.after(CombatSet),
+                handle_score_system,
+                repair_sword_system
+                    .after(CombatSet)
+                    .run_if(in_state(PauseState::Running)),
+            )
+                .run_if(in_state(GameState::InGame)),
         );
 
         app.add_systems(
             OnEnter(GameState::InGame),
-            (setup_score_manager).run_if(in_state(GameState::InGame)),
+            (setup_score_manager, setup_sword_system).run_if(in_state(GameState::InGame)),
         );
 
         app.add_systems(OnExit(GameState::InGame), (update_point_system));
@@ -32,15 +50,11 @@ impl Plugin for GameManagerPlugin {
 
 #[derive(Component)]
 pub struct Scorer {
-    pub incoming: VecDeque<i32>,
     pub current: i32,
 }
 
 fn setup_score_manager(mut commands: Commands) {
-    commands.spawn((Scorer {
-        current: 0,
-        incoming: VecDeque::new(),
-    },));
+    commands.spawn((Scorer { current: 0 },));
 }
 
 fn update_point_system(
@@ -49,26 +63,102 @@ fn update_point_system(
     mut pkv: ResMut<PkvStore>,
 ) {
     if let Ok((entity, scorer)) = scorer_query.get_single() {
-        let points_so_far = get_sotred_value(&mut pkv, "score");
-        store_value(&mut pkv, "score", points_so_far + scorer.current);
+        let mut save_data = load_save_data(&mut pkv);
+        save_data.score += scorer.current;
+        store_save_data(&mut pkv, &save_data);
         commands.entity(entity).despawn();
     }
 }
 
-fn handle_score_system(mut scorer_query: Query<&mut Scorer>) {
+fn handle_score_system(
+    mut killed_events: EventReader<EntityKilled>,
+    mut scorer_query: Query<&mut Scorer>,
+) {
     if let Ok(mut scorer) = scorer_query.get_single_mut() {
-        while let Some(income) = scorer.incoming.pop_front() {
-            scorer.current += income;
+        for killed in killed_events.read() {
+            scorer.current += killed.points;
+        }
+    }
+}
+
+//the thing the heroes are after, the run is lost once it breaks
+#[derive(Component)]
+pub struct Sword {
+    //health per second while no hero is around
+    pub repair: f32,
+}
+
+fn setup_sword_system(
+    mut commands: Commands,
+    game_data: Res<GameData>,
+    game_datas: Res<Assets<GameInfos>>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    let save_data = load_save_data(&mut pkv);
+    let mut health = DEFAULT_SWORD_HEALTH;
+    let mut repair = 0.0;
+    if let Some(game_data) = game_datas.get(game_data.data.id()) {
+        health = level_value(
+            &game_data.sword_health,
+            save_data.level(Upgrade::SwordDurability),
+            health,
+        );
+        repair = level_value(
+            &game_data.sword_repair,
+            save_data.level(Upgrade::SwordRepair),
+            repair,
+        );
+    }
+
+    //nothing bumps into it, and its own hit layer keeps boss stomps and charges off it, only hero weapons reach it
+    let sword = commands
+        .spawn((
+            SceneObject,
+            Sword { repair },
+            Transform::from_xyz(SWORD_POSITION.x, SWORD_POSITION.y, 0.0),
+            RigidBody::Static,
+            Collider::rectangle(0.5, 2.0),
+            CollisionLayers::new(GameLayer::Player, LayerMask::NONE),
+            LinearVelocity::default(),
+            Opfer::new(2, 0.0, 0.0, 0.0),
+            Health::from_health(health),
+        ))
+        .id();
+    commands.spawn((
+        SceneObject,
+        HealthBarInitiator {
+            enity: sword,
+            name: "Sword".to_string(),
+            bottom: true,
+        },
+    ));
+}
+
+fn repair_sword_system(
+    time: Res<Time>,
+    hit_detection: Res<HitDetection>,
+    mut sword_query: Query<(&Sword, &Transform, &mut Health), Without<Dead>>,
+) {
+    for (sword, transform, mut health) in sword_query.iter_mut() {
+        if (sword.repair <= 0.0 || health.health >= health.max_health) {
+            continue;
+        }
+        let x = transform.translation.x;
+        if (hit_detection
+            .nearest(Faction::Hero, x, REPAIR_CALM_RADIUS)
+            .is_some())
+        {
+            continue;
         }
+        health.health = (health.health + sword.repair * time.delta_secs()).min(health.max_health);
     }
 }
+
 fn check_lose_system(
-    enemy_query: Query<&Transform, With<Enemy>>,
+    sword_query: Query<(), (With<Sword>, With<Dead>)>,
     mut game_state: ResMut<NextState<GameState>>,
 ) {
-    for (transform) in enemy_query.iter() {
-        if (transform.translation.x.abs() <= 0.1) {
-            game_state.set(GameState::CutScene);
-        }
+    if (!sword_query.is_empty()) {
+        game_state.set(GameState::CutScene);
     }
 }
@@ -0,0 +1,54 @@
+use crate::game_state::GameState;
+use bevy::app::{App, Plugin};
+use bevy::log::info;
+use bevy::prelude::{OnEnter, Res, ResMut, Resource};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+pub struct GameRngPlugin {
+    pub seed: Option<u64>,
+}
+
+impl Plugin for GameRngPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GameRngSettings { seed: self.seed });
+        app.insert_resource(GameRng::new(self.seed.unwrap_or_else(rand::random)));
+        app.add_systems(OnEnter(GameState::InGame), reseed_system);
+    }
+}
+
+//all gameplay randomness comes from here, so the same seed and inputs give the same run
+#[derive(Resource)]
+pub struct GameRng {
+    pub seed: u64,
+    pub rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        return GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        };
+    }
+}
+
+#[derive(Resource)]
+struct GameRngSettings {
+    //every run starts from this seed if set, otherwise each run gets a fresh one
+    seed: Option<u64>,
+}
+
+//reads `--seed <number>` from the command line
+pub fn seed_from_args() -> Option<u64> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == "--seed")?;
+    return args
+        .get(index + 1)
+        .and_then(|seed| seed.parse::<u64>().ok());
+}
+
+fn reseed_system(settings: Res<GameRngSettings>, mut game_rng: ResMut<GameRng>) {
+    *game_rng = GameRng::new(settings.seed.unwrap_or_else(rand::random));
+    info!("run seed: {}", game_rng.seed);
+}
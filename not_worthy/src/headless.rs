@@ -0,0 +1,120 @@
+use crate::game_manager::Scorer;
+use crate::game_state::GameState;
+use crate::input_manager::{Action, BasicControl};
+use crate::replay::ReplayPlayback;
+use bevy::app::{App, Plugin, PreUpdate};
+use bevy::core::FrameCount;
+use bevy::prelude::{
+    in_state, not, resource_exists, IntoSystemConfigs, Query, Res, Resource, State, With,
+};
+use bevy::time::{Time, TimeUpdateStrategy, Virtual};
+use leafwing_input_manager::plugin::InputManagerSystem;
+use leafwing_input_manager::prelude::ActionState;
+use std::time::{Duration, Instant};
+
+//simulation step used when running without a window
+const HEADLESS_STEP: f32 = 1.0 / 60.0;
+//wall clock time the assets get to load before giving up
+pub const LOADING_TIMEOUT: Duration = Duration::from_secs(30);
+
+//present when the game runs without window, renderer and audio
+#[derive(Resource)]
+pub struct Headless {
+    pub seconds: f32,
+}
+
+pub struct HeadlessPlugin {
+    pub seconds: f32,
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Headless {
+            seconds: self.seconds,
+        });
+        app.insert_resource(headless_time_strategy());
+        app.add_systems(
+            PreUpdate,
+            autopilot_system
+                .in_set(InputManagerSystem::ManualControl)
+                .run_if(in_state(GameState::InGame))
+                .run_if(not(resource_exists::<ReplayPlayback>)),
+        );
+    }
+}
+
+pub fn headless_time_strategy() -> TimeUpdateStrategy {
+    return TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(HEADLESS_STEP));
+}
+
+//run condition for everything that needs a renderer or audio output
+pub fn visuals_enabled(headless: Option<Res<Headless>>) -> bool {
+    return headless.is_none();
+}
+
+//reads `--headless <seconds>` from the command line
+pub fn headless_seconds() -> Option<f32> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == "--headless")?;
+    return Some(
+        args.get(index + 1)
+            .and_then(|seconds| seconds.parse::<f32>().ok())
+            .unwrap_or(60.0),
+    );
+}
+
+//nobody is pressing buttons, so keep tapping arise and attack
+//the cooldowns in the game decide how often they actually fire
+fn autopilot_system(
+    frame_count: Res<FrameCount>,
+    mut input_query: Query<&mut ActionState<Action>, With<BasicControl>>,
+) {
+    for mut action_state in input_query.iter_mut() {
+        if (frame_count.0 % 2 == 0) {
+            action_state.press(&Action::Special);
+            action_state.press(&Action::Punch);
+        } else {
+            action_state.release(&Action::Special);
+            action_state.release(&Action::Punch);
+        }
+    }
+}
+
+//steps the app until the run is lost or the simulated time is up and returns the score
+pub fn run_headless(app: &mut App) -> Option<i32> {
+    app.finish();
+    app.cleanup();
+
+    let seconds = app.world().resource::<Headless>().seconds;
+    let loading_started = Instant::now();
+    let mut started_at: Option<f32> = None;
+    let mut score = 0;
+    loop {
+        app.update();
+
+        let elapsed = app.world().resource::<Time<Virtual>>().elapsed_secs();
+        let state = app.world().resource::<State<GameState>>().get().clone();
+        if (state == GameState::InGame) {
+            if (started_at.is_none()) {
+                started_at = Some(elapsed);
+            }
+            let mut scorer_query = app.world_mut().query::<&Scorer>();
+            if let Ok(scorer) = scorer_query.get_single(app.world()) {
+                score = scorer.current;
+            }
+        }
+
+        match started_at {
+            Some(start) => {
+                if (state != GameState::InGame || elapsed - start >= seconds) {
+                    return Some(score);
+                }
+            }
+            None => {
+                if (loading_started.elapsed() > LOADING_TIMEOUT) {
+                    return None;
+                }
+            }
+        }
+    }
+}
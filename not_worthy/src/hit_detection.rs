@@ -1,23 +1,38 @@
-use crate::game_state::GameState;
-use crate::level_loading::SceneObject;
+use crate::combat::Dead;
+use crate::game_manager::Sword;
+use crate::spawning::Enemy;
 use crate::summoning::Player;
-use bevy::app::{App, FixedPreUpdate, Plugin, Update};
-use bevy::prelude::{
-    in_state, Commands, Component, IntoSystemConfigs, OnEnter, Query, SystemSet, Transform, With,
-};
+use bevy::app::{App, FixedPreUpdate, Plugin};
+use bevy::prelude::{Entity, IntoSystemConfigs, Query, ResMut, Resource, Transform, With, Without};
 
-const LEFT_MOST: f32 = -10.0;
-const RIGHT_MOST: f32 = 10.0;
-const RESOLUTION: usize = 100;
+//the spawners sit at +-12, so this covers them with some room
+const LEFT_MOST: f32 = -14.0;
+const RIGHT_MOST: f32 = 14.0;
+const RESOLUTION: usize = 140;
 
-pub struct HitDetectionPlugin;
+pub struct HitDetectionPlugin {
+    pub left_most: f32,
+    pub right_most: f32,
+    pub resolution: usize,
+}
+
+impl Default for HitDetectionPlugin {
+    fn default() -> Self {
+        return HitDetectionPlugin {
+            left_most: LEFT_MOST,
+            right_most: RIGHT_MOST,
+            resolution: RESOLUTION,
+        };
+    }
+}
 
 impl Plugin for HitDetectionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            OnEnter(GameState::InGame),
-            setup_hit_detection.run_if(in_state(GameState::InGame)),
-        );
+        app.insert_resource(HitDetection::new(
+            self.left_most,
+            self.right_most,
+            self.resolution,
+        ));
         app.add_systems(
             FixedPreUpdate,
             ((build_detection_vector_system).in_set(crate::combat::CombatSet)),
@@ -26,54 +41,221 @@ impl Plugin for HitDetectionPlugin {
     }
 }
 
-#[derive(Component)]
-pub struct HitDetection {
-    pub space: [usize; RESOLUTION],
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Faction {
+    Skeleton,
+    Hero,
+    //kept apart from the skeletons so the sword never counts as part of the squad
+    Sword,
 }
-pub fn get_space_coord(pos: f32) -> usize {
-    return (((pos - LEFT_MOST) * ((RESOLUTION as f32) / (RIGHT_MOST - LEFT_MOST))) as usize)
-        .clamp(0, RESOLUTION - 1);
+
+//1d index over x, every bucket lists the entities in it together with their exact position
+#[derive(Resource)]
+pub struct HitDetection {
+    left_most: f32,
+    right_most: f32,
+    skeletons: Vec<Vec<(Entity, f32)>>,
+    heroes: Vec<Vec<(Entity, f32)>>,
+    swords: Vec<Vec<(Entity, f32)>>,
 }
-fn fill_space(space: &mut [usize; RESOLUTION], pos: f32) {
-    let coord: usize = get_space_coord(pos);
 
-    space[coord] += 1;
-    let left = coord.saturating_sub(1);
-    let right = coord.saturating_add(1).min(RESOLUTION - 1);
+impl HitDetection {
+    pub fn new(left_most: f32, right_most: f32, resolution: usize) -> Self {
+        let resolution = resolution.max(1);
+        return HitDetection {
+            left_most,
+            right_most,
+            skeletons: vec![Vec::new(); resolution],
+            heroes: vec![Vec::new(); resolution],
+            swords: vec![Vec::new(); resolution],
+        };
+    }
+
+    pub fn resolution(&self) -> usize {
+        return self.skeletons.len();
+    }
 
-    space[left] += (left != coord) as usize;
-    space[right] += (right != coord) as usize;
-}
+    fn bucket_width(&self) -> f32 {
+        return (self.right_most - self.left_most) / self.resolution() as f32;
+    }
 
-pub fn test_point(space: &[usize; 100], pos: f32) -> bool {
-    if (pos < LEFT_MOST || pos > RIGHT_MOST) {
-        return false;
+    //positions outside the bounds end up in the outermost buckets
+    pub fn get_space_coord(&self, pos: f32) -> usize {
+        let coord = ((pos - self.left_most) / self.bucket_width()).max(0.0) as usize;
+        return coord.min(self.resolution() - 1);
     }
-    let coord = get_space_coord(pos);
-    if (space[coord] > 0) {
-        return true;
+
+    fn buckets(&self, faction: Faction) -> &Vec<Vec<(Entity, f32)>> {
+        match faction {
+            Faction::Skeleton => &self.skeletons,
+            Faction::Hero => &self.heroes,
+            Faction::Sword => &self.swords,
+        }
+    }
+
+    fn clear(&mut self) {
+        for bucket in self
+            .skeletons
+            .iter_mut()
+            .chain(self.heroes.iter_mut())
+            .chain(self.swords.iter_mut())
+        {
+            bucket.clear();
+        }
+    }
+
+    pub fn insert(&mut self, faction: Faction, entity: Entity, pos: f32) {
+        let coord = self.get_space_coord(pos);
+        match faction {
+            Faction::Skeleton => self.skeletons[coord].push((entity, pos)),
+            Faction::Hero => self.heroes[coord].push((entity, pos)),
+            Faction::Sword => self.swords[coord].push((entity, pos)),
+        }
+    }
+
+    //true if something of the faction is in the bucket of pos or right next to it
+    pub fn test_point(&self, faction: Faction, pos: f32) -> bool {
+        if (pos < self.left_most || pos > self.right_most) {
+            return false;
+        }
+        let coord = self.get_space_coord(pos);
+        let left = coord.saturating_sub(1);
+        let right = (coord + 1).min(self.resolution() - 1);
+        return self.buckets(faction)[left..=right]
+            .iter()
+            .any(|bucket| !bucket.is_empty());
     }
-    return false;
-}
 
-fn setup_hit_detection(mut commands: Commands) {
-    commands.spawn((
-        HitDetection {
-            space: [0; RESOLUTION],
-        },
-        SceneObject,
-    ));
+    //closest entity of the faction within radius, searching the buckets outwards from pos
+    pub fn nearest(&self, faction: Faction, pos: f32, radius: f32) -> Option<(Entity, f32)> {
+        let buckets = self.buckets(faction);
+        let coord = self.get_space_coord(pos) as isize;
+        let reach = (radius / self.bucket_width()).ceil() as isize + 1;
+        let mut nearest: Option<(Entity, f32)> = None;
+        for step in 0..=reach {
+            //nothing further out can be closer than what was already found
+            if let Some((_, distance)) = nearest {
+                if ((step - 1) as f32 * self.bucket_width() > distance) {
+                    break;
+                }
+            }
+            let indices = if (step == 0) {
+                vec![coord]
+            } else {
+                vec![coord - step, coord + step]
+            };
+            for index in indices {
+                if (index < 0 || index as usize >= buckets.len()) {
+                    continue;
+                }
+                for (entity, entity_pos) in buckets[index as usize].iter() {
+                    let distance = (entity_pos - pos).abs();
+                    if (distance <= radius
+                        && nearest
+                            .map_or(true, |(_, nearest_distance)| distance < nearest_distance))
+                    {
+                        nearest = Some((*entity, distance));
+                    }
+                }
+            }
+        }
+        return nearest;
+    }
 }
+
 fn build_detection_vector_system(
-    mut hit_detection_query: Query<&mut HitDetection>,
-    player_query: Query<&Transform, With<Player>>,
+    mut hit_detection: ResMut<HitDetection>,
+    player_query: Query<(Entity, &Transform), (With<Player>, Without<Dead>)>,
+    sword_query: Query<(Entity, &Transform), (With<Sword>, Without<Dead>)>,
+    enemy_query: Query<(Entity, &Transform), (With<Enemy>, Without<Dead>)>,
 ) {
-    if let Ok(mut hit_detection) = hit_detection_query.get_single_mut() {
-        for elem in hit_detection.space.iter_mut() {
-            *elem = 0;
-        }
-        for (transform) in player_query.iter() {
-            fill_space(&mut hit_detection.space, transform.translation.x);
-        }
+    hit_detection.clear();
+    for (entity, transform) in player_query.iter() {
+        hit_detection.insert(Faction::Skeleton, entity, transform.translation.x);
+    }
+    for (entity, transform) in sword_query.iter() {
+        hit_detection.insert(Faction::Sword, entity, transform.translation.x);
+    }
+    for (entity, transform) in enemy_query.iter() {
+        hit_detection.insert(Faction::Hero, entity, transform.translation.x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //one world unit per bucket, bucket 10 covers 0..1
+    fn unit_buckets() -> HitDetection {
+        return HitDetection::new(-10.0, 10.0, 20);
+    }
+
+    #[test]
+    fn nearest_looks_past_the_first_bucket_with_something_in_it() {
+        let mut hit_detection = unit_buckets();
+        let same_bucket = Entity::from_raw(1);
+        let neighbour = Entity::from_raw(2);
+        hit_detection.insert(Faction::Skeleton, same_bucket, 0.05);
+        hit_detection.insert(Faction::Skeleton, neighbour, 1.1);
+        assert_eq!(
+            hit_detection.nearest(Faction::Skeleton, 0.9, 5.0),
+            Some((neighbour, 1.1 - 0.9))
+        );
+
+        let mut hit_detection = unit_buckets();
+        let one_out = Entity::from_raw(1);
+        let two_out = Entity::from_raw(2);
+        hit_detection.insert(Faction::Skeleton, one_out, -0.95);
+        hit_detection.insert(Faction::Skeleton, two_out, 2.05);
+        let (entity, _) = hit_detection.nearest(Faction::Skeleton, 0.9, 5.0).unwrap();
+        assert_eq!(entity, two_out);
+    }
+
+    #[test]
+    fn nearest_ignores_everything_past_the_radius() {
+        let mut hit_detection = unit_buckets();
+        let entity = Entity::from_raw(1);
+        hit_detection.insert(Faction::Hero, entity, 3.0);
+        assert_eq!(hit_detection.nearest(Faction::Hero, 0.0, 2.0), None);
+        assert_eq!(
+            hit_detection.nearest(Faction::Hero, 0.0, 3.5),
+            Some((entity, 3.0))
+        );
+    }
+
+    #[test]
+    fn positions_out_of_bounds_end_up_in_the_edge_buckets() {
+        let mut hit_detection = unit_buckets();
+        assert_eq!(hit_detection.get_space_coord(-1000.0), 0);
+        assert_eq!(
+            hit_detection.get_space_coord(1000.0),
+            hit_detection.resolution() - 1
+        );
+
+        let far_right = Entity::from_raw(1);
+        hit_detection.insert(Faction::Skeleton, far_right, 100.0);
+        hit_detection.insert(Faction::Skeleton, Entity::from_raw(2), -100.0);
+        assert_eq!(
+            hit_detection.nearest(Faction::Skeleton, 50.0, 60.0),
+            Some((far_right, 50.0))
+        );
+        assert!(!hit_detection.test_point(Faction::Skeleton, 100.0));
+        assert!(!hit_detection.test_point(Faction::Skeleton, -100.0));
+        assert!(hit_detection.test_point(Faction::Skeleton, 9.9));
+    }
+
+    #[test]
+    fn factions_are_kept_apart() {
+        let mut hit_detection = unit_buckets();
+        let sword = Entity::from_raw(1);
+        hit_detection.insert(Faction::Sword, sword, 0.0);
+        assert_eq!(hit_detection.nearest(Faction::Skeleton, 0.0, 5.0), None);
+        assert_eq!(hit_detection.nearest(Faction::Hero, 0.0, 5.0), None);
+        assert!(!hit_detection.test_point(Faction::Skeleton, 0.0));
+        assert_eq!(
+            hit_detection.nearest(Faction::Sword, 0.0, 5.0),
+            Some((sword, 0.0))
+        );
+        assert!(hit_detection.test_point(Faction::Sword, 0.0));
     }
 }
@@ -1,5 +1,8 @@
-use bevy::app::{App, Plugin};
-use bevy::prelude::{Commands, Component, GamepadButton, KeyCode, Reflect, Startup};
+use bevy::app::{App, FixedLast, Plugin, PreUpdate};
+use bevy::prelude::{
+    Commands, Component, GamepadButton, IntoSystemConfigs, KeyCode, Query, Reflect, Startup,
+};
+use leafwing_input_manager::plugin::InputManagerSystem;
 use leafwing_input_manager::prelude::*;
 use leafwing_input_manager::user_input::GamepadStick;
 
@@ -9,6 +12,12 @@ impl Plugin for InputManagingPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(InputManagerPlugin::<Action>::default());
         app.add_systems(Startup, spawn_input_manager);
+        //after ManualControl, so autopilot and replays are latched as well
+        app.add_systems(
+            PreUpdate,
+            latch_input_system.after(InputManagerSystem::ManualControl),
+        );
+        app.add_systems(FixedLast, consume_input_system);
         // add things to your app here
     }
 }
@@ -20,10 +29,57 @@ pub enum Action {
     Punch,
     #[actionlike(Button)]
     Special,
+    //switches the skeletons between following the input and fighting on their own
+    #[actionlike(Button)]
+    Command,
+    //move axis of the right group while the squad is split
+    #[actionlike(Axis)]
+    MoveRight,
+    //splits the skeletons into a left and a right group of the sword
+    #[actionlike(Button)]
+    Split,
+    //cycles which corpses arise picks
+    #[actionlike(Button)]
+    CycleTargeting,
 }
 
 #[derive(Component)]
 pub struct BasicControl {}
+
+//input as the fixed timestep sees it
+//a frame can run zero or several fixed ticks, so presses are kept until a tick used them
+#[derive(Component, Default)]
+pub struct GameplayInput {
+    pub movement: f32,
+    pub punch: bool,
+    pub special: bool,
+    pub command: bool,
+    pub right_movement: f32,
+    pub split: bool,
+    pub cycle_targeting: bool,
+}
+
+fn latch_input_system(mut input_query: Query<(&ActionState<Action>, &mut GameplayInput)>) {
+    for (action_state, mut input) in input_query.iter_mut() {
+        input.movement = action_state.clamped_value(&Action::Move);
+        input.punch |= action_state.just_pressed(&Action::Punch);
+        input.special |= action_state.just_pressed(&Action::Special);
+        input.command |= action_state.just_pressed(&Action::Command);
+        input.right_movement = action_state.clamped_value(&Action::MoveRight);
+        input.split |= action_state.just_pressed(&Action::Split);
+        input.cycle_targeting |= action_state.just_pressed(&Action::CycleTargeting);
+    }
+}
+
+fn consume_input_system(mut input_query: Query<&mut GameplayInput>) {
+    for mut input in input_query.iter_mut() {
+        input.punch = false;
+        input.special = false;
+        input.command = false;
+        input.split = false;
+        input.cycle_targeting = false;
+    }
+}
 fn spawn_input_manager(mut commands: Commands) {
     let mut input_map = InputMap::default()
         .with_axis(Action::Move, GamepadControlAxis::LEFT_X)
@@ -31,7 +87,19 @@ fn spawn_input_manager(mut commands: Commands) {
         .with(Action::Punch, GamepadButton::South)
         .with(Action::Punch, KeyCode::KeyJ)
         .with(Action::Special, GamepadButton::East)
-        .with(Action::Special, KeyCode::Space);
+        .with(Action::Special, KeyCode::Space)
+        .with(Action::Command, GamepadButton::North)
+        .with(Action::Command, KeyCode::KeyK)
+        .with_axis(Action::MoveRight, GamepadControlAxis::RIGHT_X)
+        .with_axis(Action::MoveRight, VirtualAxis::horizontal_arrow_keys())
+        .with(Action::Split, GamepadButton::West)
+        .with(Action::Split, KeyCode::KeyL)
+        .with(Action::CycleTargeting, GamepadButton::RightTrigger)
+        .with(Action::CycleTargeting, KeyCode::KeyI);
     // input_map.insert_axis(Action::Move, VirtualAxis::ad());
-    commands.spawn((InputManagerBundle::with_map(input_map), BasicControl {}));
+    commands.spawn((
+        InputManagerBundle::with_map(input_map),
+        BasicControl {},
+        GameplayInput::default(),
+    ));
 }
@@ -1,10 +1,11 @@
 use crate::animation::AnimationTimer;
 use crate::asset_load::{
-    CutSceneArt, CutSceneSounds, EnemySounds, EnemySprite, EnvironmentArt, MusicAssets,
-    SkeletonSprite, SwordAnimation,
+    CutSceneArt, CutSceneSounds, EnemySounds, EnemySprite, EnvironmentArt, LevelAssets, LevelInfo,
+    MusicAssets, SkeletonSprite, SwordAnimation,
 };
 use crate::combat::CombatPlugin;
 use crate::game_state::{GameState, PauseState};
+use crate::headless::visuals_enabled;
 use crate::spawning::{EnemySpawner, EnemyType};
 use crate::summoning::{spawn_deceased, spawn_player, DeceasedSpawnPoint};
 use crate::ui_stuff::CompText;
@@ -16,21 +17,22 @@ use bevy::audio::{AudioPlayer, AudioSource, PlaybackMode, PlaybackSettings, Volu
 use bevy::color::Color;
 use bevy::core_pipeline::bloom::Bloom;
 use bevy::input::mouse::{MouseButtonInput, MouseWheel};
+use bevy::log::warn;
 use bevy::math::{Vec2, Vec3};
 use bevy::pbr::{AmbientLight, PointLight};
 use bevy::prelude::{
     default, in_state, resource_changed, Alpha, AlphaMode, AssetServer, Assets, AudioBundle,
     Camera, Camera3d, Commands, Component, DespawnRecursiveExt, Entity, EventReader,
     GlobalTransform, Handle, IntoSystemConfigs, LinearRgba, Msaa, Name, NextState, OnEnter, OnExit,
-    Query, Res, ResMut, SystemSet, Text, TextureAtlas, Time, Timer, TimerMode, Transform, Vec4,
-    Window, With, Without,
+    Query, Res, ResMut, Resource, SystemSet, Text, TextureAtlas, Time, Timer, TimerMode, Transform,
+    Vec4, Window, With, Without,
 };
 use bevy::window::PrimaryWindow;
 use bevy_firework::bevy_utilitarian::prelude::{RandF32, RandVec3};
 use bevy_firework::core::{BlendMode, ParticleSpawner};
 use bevy_firework::curve::{FireworkCurve, FireworkGradient};
 use bevy_firework::emission_shape::EmissionShape;
-use bevy_pipelines_ready::{PipelinesReady, PipelinesReadyPlugin};
+use bevy_pipelines_ready::PipelinesReady;
 use bevy_sprite3d::{Sprite3dBuilder, Sprite3dParams};
 use std::f32::consts::PI;
 use std::time::Duration;
@@ -41,13 +43,16 @@ pub struct LevelLoadingPlugin;
 impl Plugin for LevelLoadingPlugin {
     fn build(&self, app: &mut App) {
         println!("test");
+        app.init_resource::<SelectedLevel>();
         //debug
         // app.add_systems(Update, (asset_placer_sytem));
         app.add_systems(
             OnEnter(GameState::InGame),
             (
                 setup.run_if(in_state(GameState::InGame)),
-                summon_world.run_if(in_state(GameState::InGame)),
+                (setup_view, summon_world)
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(visuals_enabled),
             ),
         );
         app.add_systems(
@@ -59,7 +64,9 @@ impl Plugin for LevelLoadingPlugin {
         );
         app.add_systems(
             OnEnter(GameState::CutScene),
-            (setup_cut_scene.run_if(in_state(GameState::CutScene)),),
+            (setup_cut_scene
+                .run_if(in_state(GameState::CutScene))
+                .run_if(visuals_enabled),),
         );
 
         app.add_systems(
@@ -69,7 +76,9 @@ impl Plugin for LevelLoadingPlugin {
 
         app.add_systems(
             OnEnter(GameState::Loading),
-            (setup_loading.run_if(in_state(GameState::Loading)),),
+            (setup_loading
+                .run_if(in_state(GameState::Loading))
+                .run_if(visuals_enabled),),
         );
 
         app.add_systems(
@@ -87,16 +96,18 @@ impl Plugin for LevelLoadingPlugin {
                 summon_world.run_if(in_state(GameState::CompilingShaders)),
             ),
         );
-        app.add_plugins(PipelinesReadyPlugin);
         app.add_systems(
             Update,
             check_ready.run_if(in_state(GameState::CompilingShaders)),
         );
 
-        app.add_systems(Startup, setup_necessary);
+        app.add_systems(Startup, setup_necessary.run_if(visuals_enabled));
         app.add_systems(OnExit(GameState::Menu), (delete_everything));
         app.add_systems(OnExit(GameState::CompilingShaders), (delete_everything));
-        app.add_systems(OnExit(GameState::Loading), (delete_everything));
+        app.add_systems(
+            OnExit(GameState::Loading),
+            (delete_everything, select_level_system),
+        );
         app.add_systems(OnExit(GameState::InGame), (delete_everything));
         app.add_systems(OnExit(GameState::CutScene), (delete_everything));
         app.add_systems(OnExit(GameState::Shop), (delete_everything));
@@ -106,7 +117,7 @@ impl Plugin for LevelLoadingPlugin {
 pub struct SceneObject;
 
 #[derive(Component)]
-struct MainCamera;
+pub struct MainCamera;
 
 fn setup_mock_world(mut commands: Commands) {
     commands.spawn((
@@ -131,24 +142,125 @@ fn check_ready(
     }
 }
 
+#[derive(Resource, Default)]
+pub struct SelectedLevel {
+    pub index: usize,
+    //level asked for by name, turned into the index once the levels are loaded
+    pub name: Option<String>,
+}
+
+pub fn level_from_args() -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == "--level")?;
+    return args.get(index + 1).cloned();
+}
+
+//takes the name from the level file or the file name without .level.json
+fn select_level_system(
+    mut level_assets: ResMut<LevelAssets>,
+    level_infos: Res<Assets<LevelInfo>>,
+    mut selected_level: ResMut<SelectedLevel>,
+) {
+    level_assets
+        .levels
+        .sort_by_key(|handle| handle.path().map(|path| path.to_string()));
+    let Some(name) = selected_level.name.take() else {
+        return;
+    };
+    let file_name = format!("{}.level.json", name);
+    let index = level_assets.levels.iter().position(|handle| {
+        let named = level_infos
+            .get(handle.id())
+            .map_or(false, |level| level.name == name);
+        let file = handle
+            .path()
+            .and_then(|path| path.path().file_name())
+            .map_or(false, |file| file.to_string_lossy() == file_name);
+        return named || file;
+    });
+    match index {
+        Some(index) => selected_level.index = index,
+        None => warn!(
+            "no level named {}, playing level {}",
+            name, selected_level.index
+        ),
+    }
+}
+
+fn get_selected_level<'a>(
+    level_assets: &LevelAssets,
+    level_infos: &'a Assets<LevelInfo>,
+    selected_level: &SelectedLevel,
+) -> Option<&'a LevelInfo> {
+    return level_assets
+        .levels
+        .get(selected_level.index)
+        .and_then(|handle| level_infos.get(handle.id()));
+}
+
 fn setup(
     mut commands: Commands,
-    enemy_sounds: Res<EnemySounds>,
-    music_assets: Res<MusicAssets>,
+    level_assets: Res<LevelAssets>,
+    level_infos: Res<Assets<LevelInfo>>,
+    selected_level: Res<SelectedLevel>,
     mut paused_state: ResMut<NextState<PauseState>>,
 ) {
     paused_state.set(PauseState::Paused);
+    let Some(level) = get_selected_level(&level_assets, &level_infos, &selected_level) else {
+        warn!("no level at index {}", selected_level.index);
+        return;
+    };
+
     commands.spawn((
-        AudioPlayer::new(music_assets.in_game.clone()),
-        PlaybackSettings {
-            volume: Volume::new(0.4),
-            mode: PlaybackMode::Loop,
-            ..Default::default()
-        },
+        Transform::from_translation(Vec3::from(level.ground.position)).with_scale(Vec3::new(
+            level.ground.size[0],
+            level.ground.size[1],
+            1.0,
+        )),
+        RigidBody::Static,
+        Collider::rectangle(1.0, 1.0),
         SceneObject,
     ));
-    play_game_music(&mut commands, music_assets);
 
+    for deceased in level.deceased.iter() {
+        commands.spawn((
+            SceneObject {},
+            DeceasedSpawnPoint {
+                enemy_type: deceased.enemy_type.clone(),
+                resurrections: 0,
+            },
+            Transform::from_translation(Vec3::from(deceased.position)),
+        ));
+    }
+
+    for spawner in level.spawners.iter() {
+        commands.spawn((
+            Transform::from_translation(Vec3::from(spawner.position)),
+            EnemySpawner {
+                once: spawner.once,
+                inactive: Timer::new(Duration::from_secs_f32(spawner.inactive), TimerMode::Once),
+                preheat: spawner.preheat,
+                min: spawner.min,
+                max: spawner.max,
+                factor: spawner.factor,
+                timer: Timer::new(
+                    Duration::from_secs_f32(spawner.interval),
+                    TimerMode::Repeating,
+                ),
+                enemy_type: spawner.enemy_type.clone(),
+            },
+            SceneObject,
+        ));
+    }
+}
+
+fn setup_view(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level_assets: Res<LevelAssets>,
+    level_infos: Res<Assets<LevelInfo>>,
+    selected_level: Res<SelectedLevel>,
+) {
     // commands.spawn((
     //     Camera3d::default(),
     //     MainCamera,
@@ -162,139 +274,25 @@ fn setup(
         Msaa::Off,
     ));
 
+    let Some(level) = get_selected_level(&level_assets, &level_infos, &selected_level) else {
+        return;
+    };
     commands.spawn((
-        Transform::from_xyz(0.0, -1.0, -2.0).with_scale(Vec3::new(1000.0, 1.0, 1.0)),
-        RigidBody::Static,
-        Collider::rectangle(1.0, 1.0),
-        SceneObject,
-    ));
-
-    commands.spawn((
-        SceneObject {},
-        DeceasedSpawnPoint {
-            enemy_type: EnemyType::BASIC,
-        },
-        Transform::from_xyz(1.5, 0.0, 0.0),
-    ));
-
-    // if (true) {
-    //     for i in -50..50 {
-    //         commands.spawn((
-    //             SceneObject {},
-    //             DeceasedSpawnPoint {
-    //                 enemy_type: EnemyType::BASIC,
-    //             },
-    //             Transform::from_xyz(6.0 * i as f32 / 50.0, 0.0, 0.0),
-    //         ));
-    //     }
-    // }
-    commands.spawn((
-        Transform::from_xyz(10.0, 2.00, 0.0),
-        EnemySpawner {
-            once: false,
-            inactive: Timer::new(Duration::from_secs_f32(5.0), TimerMode::Once),
-            preheat: 0.0,
-            min: 0.2,
-            max: 10.0,
-            factor: 0.9,
-            timer: Timer::new(Duration::from_secs_f32(5.0), TimerMode::Repeating),
-            enemy_type: EnemyType::BASIC,
-        },
-        SceneObject,
-    ));
-
-    commands.spawn((
-        Transform::from_xyz(-10.0, 2.00, 0.0),
-        EnemySpawner {
-            once: false,
-            inactive: Timer::new(Duration::from_secs_f32(10.0), TimerMode::Once),
-            preheat: 0.0,
-            min: 0.2,
-            max: 10.0,
-            factor: 0.9,
-            timer: Timer::new(Duration::from_secs_f32(5.0), TimerMode::Repeating),
-            enemy_type: EnemyType::BASIC,
-        },
-        SceneObject,
-    ));
-    commands.spawn((
-        Transform::from_xyz(-10.0, 0.00, 0.0),
-        EnemySpawner {
-            once: false,
-            inactive: Timer::new(Duration::from_secs_f32(1.0), TimerMode::Once),
-            preheat: 0.1,
-            min: 0.1,
-            max: 10.0,
-            factor: 1.1,
-            timer: Timer::new(Duration::from_secs_f32(5.0), TimerMode::Repeating),
-            enemy_type: EnemyType::FAST,
-        },
-        SceneObject,
-    ));
-
-    commands.spawn((
-        Transform::from_xyz(10.0, 0.00, 0.0),
-        EnemySpawner {
-            once: false,
-            inactive: Timer::default(),
-            preheat: 0.1,
-            min: 0.1,
-            max: 10.0,
-            factor: 1.1,
-            timer: Timer::new(Duration::from_secs_f32(5.0), TimerMode::Repeating),
-            enemy_type: EnemyType::FAST,
-        },
-        SceneObject,
-    ));
-    commands.spawn((
-        Transform::from_xyz(-12.0, 2.00, 0.0),
-        EnemySpawner {
-            once: false,
-            inactive: Timer::new(Duration::from_secs_f32(50.0), TimerMode::Once),
-            preheat: 2.0,
-            min: 1.0,
-            max: 10.0,
-            factor: 0.9,
-            timer: Timer::new(Duration::from_secs_f32(45.0), TimerMode::Repeating),
-            enemy_type: EnemyType::BIG,
-        },
-        SceneObject,
-    ));
-
-    commands.spawn((
-        Transform::from_xyz(12.0, 2.00, 0.0),
-        EnemySpawner {
-            once: false,
-            inactive: Timer::new(Duration::from_secs_f32(20.0), TimerMode::Once),
-            preheat: 0.0,
-            min: 1.0,
-            max: 10.0,
-            factor: 0.9,
-            timer: Timer::new(Duration::from_secs_f32(50.0), TimerMode::Repeating),
-            enemy_type: EnemyType::BIG,
-        },
-        SceneObject,
-    ));
-    commands.spawn((
-        Transform::from_xyz(8.50, 2.00, 0.0),
-        EnemySpawner {
-            once: false,
-            inactive: Timer::new(Duration::from_secs_f32(00.0), TimerMode::Once),
-            preheat: 0.0,
-            min: 1.0,
-            max: 100.0,
-            factor: 0.9,
-            timer: Timer::new(Duration::from_secs_f32(100.0), TimerMode::Repeating),
-            enemy_type: EnemyType::BOSS,
+        AudioPlayer::<AudioSource>::new(asset_server.load(&level.music)),
+        PlaybackSettings {
+            volume: Volume::new(0.4),
+            mode: PlaybackMode::Loop,
+            ..Default::default()
         },
         SceneObject,
     ));
+    play_game_music(&mut commands, &asset_server, &level.music);
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn play_game_music(commands: &mut Commands, music_assets: Res<MusicAssets>) {
+fn play_game_music(commands: &mut Commands, asset_server: &AssetServer, music: &str) {
     commands.spawn((
-        AudioPlayer::new(music_assets.in_game.clone()),
+        AudioPlayer::<AudioSource>::new(asset_server.load(music)),
         PlaybackSettings {
             mode: PlaybackMode::Loop,
             ..Default::default()
@@ -304,8 +302,8 @@ fn play_game_music(commands: &mut Commands, music_assets: Res<MusicAssets>) {
 }
 
 #[cfg(target_arch = "wasm32")]
-fn play_game_music(commands: &mut Commands, music_assets: Res<MusicAssets>) {
-    play_music("assets/music/GrumpySworrd_intense.wav");
+fn play_game_music(commands: &mut Commands, asset_server: &AssetServer, music: &str) {
+    play_music(&format!("assets/{}", music));
 }
 
 fn delete_everything(query: Query<Entity, With<SceneObject>>, mut commands: Commands) {
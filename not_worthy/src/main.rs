@@ -1,17 +1,23 @@
 mod animation;
 mod asset_load;
 mod combat;
+mod damage_numbers;
 mod effects;
 mod end_boss;
 mod enemy;
 mod game_manager;
+mod game_rng;
 mod game_state;
+mod headless;
 mod hit_detection;
 mod input_manager;
 mod level_loading;
 mod movement;
 mod player_states;
+mod replay;
+mod save_transfer;
 mod shadows;
+mod skeleton_ai;
 mod spawning;
 mod state_handling;
 mod summoning;
@@ -19,37 +25,48 @@ mod ui_stuff;
 
 use crate::animation::{AnimationTimer, SpriteAnimationPlugin};
 use crate::asset_load::{
-    BossSprite, CutSceneArt, CutSceneSounds, EnemySounds, EnemySprite, EnvironmentArt, GameData,
-    GameInfos, Messages, MusicAssets, PlayerSounds, ShadowSprite, SkeletonSprite, SwordAnimation,
-    UIAssets, UISounds,
+    BossSprite, CutSceneArt, CutSceneSounds, EnemyInfos, EnemySounds, EnemySprite, EnvironmentArt,
+    GameData, GameInfos, LevelAssets, LevelInfo, Messages, MusicAssets, PlayerSounds, ShadowSprite,
+    SkeletonSprite, SwordAnimation, UIAssets, UISounds,
 };
 use crate::combat::{CombatPlugin, Hitter, Opfer};
+use crate::damage_numbers::DamageNumberPlugin;
 use crate::effects::EffectPlugin;
 use crate::end_boss::BossPlugin;
 use crate::enemy::{BacicEnemActiveState, BasicEnemStateMachine, EnemyPlugin, Target, Walker};
 use crate::game_manager::GameManagerPlugin;
+use crate::game_rng::{seed_from_args, GameRngPlugin};
 use crate::game_state::{GameState, PauseState};
-use crate::hit_detection::{HitDetection, HitDetectionPlugin};
+use crate::headless::{headless_seconds, run_headless, HeadlessPlugin};
+use crate::hit_detection::HitDetectionPlugin;
 use crate::input_manager::InputManagingPlugin;
-use crate::level_loading::LevelLoadingPlugin;
+use crate::level_loading::{level_from_args, LevelLoadingPlugin, SelectedLevel};
 use crate::movement::{
     get_enemy_collision_layers, get_player_collision_layers, Controllable, MovementPlugin,
 };
 use crate::player_states::PlayerPlugin;
+use crate::replay::{replay_mode_from_args, ReplayPlugin};
+use crate::save_transfer::{run_save_transfer, save_transfer_from_args};
 use crate::shadows::ShadowPlugin;
+use crate::skeleton_ai::SkeletonAiPlugin;
 use crate::spawning::{EnemySpawner, SpawningPlugin};
+use crate::state_handling::open_save_store;
 use crate::summoning::{spawn_player, SummoningPlugin};
 use crate::ui_stuff::UIStuffPlugin;
 use crate::GameState::Loading;
 use avian2d::prelude::{
-    Collider, CollisionLayers, Gravity, LockedAxes, MassPropertiesBundle, RigidBody,
+    Collider, CollisionLayers, Gravity, LockedAxes, MassPropertiesBundle,
+    PhysicsInterpolationPlugin, RigidBody,
 };
 use avian2d::PhysicsPlugins;
 use bevy::asset::AssetMetaCheck;
 use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
 use bevy::input::mouse::{MouseButtonInput, MouseWheel};
+use bevy::input::InputPlugin;
+use bevy::log::LogPlugin;
 use bevy::prelude::*;
 use bevy::render::camera::ScalingMode;
+use bevy::state::app::StatesPlugin;
 use bevy::text::cosmic_text::Motion::Up;
 use bevy::window::PrimaryWindow;
 use bevy_asset_loader::prelude::{
@@ -57,6 +74,7 @@ use bevy_asset_loader::prelude::{
 };
 use bevy_common_assets::json::JsonAssetPlugin;
 use bevy_firework::plugin::ParticleSystemPlugin;
+use bevy_pipelines_ready::PipelinesReadyPlugin;
 use bevy_pkv::PkvStore;
 use bevy_sprite3d::{Sprite3dBuilder, Sprite3dParams, Sprite3dPlugin};
 use bevy_wasm_window_resize::WindowResizePlugin;
@@ -65,68 +83,150 @@ use std::f32::consts::PI;
 use std::time::Duration;
 
 fn main() {
+    if let Some(transfer) = save_transfer_from_args() {
+        if (run_save_transfer(transfer).is_error()) {
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let mut app = App::new();
-    app.add_plugins(DefaultPlugins.set(AssetPlugin {
-        meta_check: AssetMetaCheck::Never,
-        ..default()
-    }));
-    app.add_plugins(WindowResizePlugin);
+    let headless = headless_seconds();
+    match headless {
+        Some(seconds) => add_headless_plugins(&mut app, seconds),
+        None => add_windowed_plugins(&mut app),
+    }
+
     app.add_plugins(JsonAssetPlugin::<GameInfos>::new(&["data.json"]));
+    app.add_plugins(JsonAssetPlugin::<EnemyInfos>::new(&["enemies.json"]));
+    app.add_plugins(JsonAssetPlugin::<LevelInfo>::new(&["level.json"]));
 
     app.add_plugins(JsonAssetPlugin::<Messages>::new(&["messages.json"]));
+    let replay_mode = replay_mode_from_args();
+    app.add_plugins(GameRngPlugin {
+        seed: replay_mode
+            .as_ref()
+            .and_then(|mode| mode.seed())
+            .or_else(seed_from_args),
+    });
     app.add_plugins(MovementPlugin);
     app.add_plugins(GameManagerPlugin);
-    app.add_plugins(HitDetectionPlugin);
-    app.add_plugins(ShadowPlugin);
+    app.add_plugins(HitDetectionPlugin::default());
     app.add_plugins(CombatPlugin);
     app.add_plugins(PlayerPlugin);
+    app.add_plugins(SkeletonAiPlugin);
     app.add_plugins(InputManagingPlugin);
-    app.add_plugins(EffectPlugin);
-    app.add_plugins(ParticleSystemPlugin::default());
     app.add_plugins(LevelLoadingPlugin);
+    if let Some(name) = level_from_args() {
+        app.insert_resource(SelectedLevel {
+            index: 0,
+            name: Some(name),
+        });
+    }
     app.add_plugins(SummoningPlugin);
     app.add_plugins(SpawningPlugin);
-    app.add_plugins(UIStuffPlugin);
     app.add_plugins(EnemyPlugin);
     app.add_plugins(BossPlugin);
-    app.add_plugins(SpriteAnimationPlugin);
-    app.add_plugins(PhysicsPlugins::default());
+    //gameplay runs on the fixed timestep, interpolation keeps the rendering smooth in between
+    app.add_plugins(PhysicsPlugins::default().set(PhysicsInterpolationPlugin::interpolate_all()));
+    if let Some(mode) = replay_mode {
+        app.add_plugins(ReplayPlugin { mode });
+    }
     app.insert_resource(Gravity(Vec2::new(0.0, -9.81)));
-    app.add_plugins(Sprite3dPlugin);
     app.init_state::<GameState>();
 
-    app.add_loading_state(
-        LoadingState::new(GameState::Loading)
-            .continue_to_state(GameState::CompilingShaders)
-            .load_collection::<SwordAnimation>()
-            .load_collection::<EnvironmentArt>()
-            .load_collection::<CutSceneArt>()
-            .load_collection::<UIAssets>()
-            .load_collection::<GameData>()
-            .load_collection::<EnemySounds>()
-            .load_collection::<PlayerSounds>()
-            .load_collection::<UISounds>()
-            .load_collection::<CutSceneSounds>()
-            .load_collection::<MusicAssets>()
-            // .load_collection::<DebugSprite>()
-            .load_collection::<EnemySprite>()
-            .load_collection::<BossSprite>()
-            .load_collection::<ShadowSprite>()
-            .load_collection::<SkeletonSprite>(),
-    );
+    match headless {
+        Some(_) => {
+            app.add_loading_state(
+                LoadingState::new(GameState::Loading)
+                    .continue_to_state(GameState::InGame)
+                    .load_collection::<GameData>()
+                    .load_collection::<LevelAssets>(),
+            );
+        }
+        None => {
+            app.add_loading_state(
+                LoadingState::new(GameState::Loading)
+                    .continue_to_state(GameState::CompilingShaders)
+                    .load_collection::<SwordAnimation>()
+                    .load_collection::<EnvironmentArt>()
+                    .load_collection::<CutSceneArt>()
+                    .load_collection::<UIAssets>()
+                    .load_collection::<GameData>()
+                    .load_collection::<LevelAssets>()
+                    .load_collection::<EnemySounds>()
+                    .load_collection::<PlayerSounds>()
+                    .load_collection::<UISounds>()
+                    .load_collection::<CutSceneSounds>()
+                    .load_collection::<MusicAssets>()
+                    // .load_collection::<DebugSprite>()
+                    .load_collection::<EnemySprite>()
+                    .load_collection::<BossSprite>()
+                    .load_collection::<ShadowSprite>()
+                    .load_collection::<SkeletonSprite>(),
+            );
+        }
+    }
 
     app.insert_state(PauseState::Paused);
 
+    // app.add_systems(Update, spawn_level.run_if(in_state(GameState::Menu)));
+    match headless {
+        Some(seconds) => match run_headless(&mut app) {
+            Some(score) => println!("simulated {} seconds, final score: {}", seconds, score),
+            None => {
+                eprintln!("headless run never reached the game, are the assets there?");
+                std::process::exit(1);
+            }
+        },
+        None => {
+            app.run();
+        }
+    }
+}
+
+fn add_windowed_plugins(app: &mut App) {
+    app.add_plugins(DefaultPlugins.set(AssetPlugin {
+        meta_check: AssetMetaCheck::Never,
+        ..default()
+    }));
+    app.add_plugins(WindowResizePlugin);
+    app.add_plugins(ShadowPlugin);
+    app.add_plugins(EffectPlugin);
+    app.add_plugins(DamageNumberPlugin);
+    app.add_plugins(ParticleSystemPlugin::default());
+    app.add_plugins(UIStuffPlugin);
+    app.add_plugins(SpriteAnimationPlugin);
+    app.add_plugins(Sprite3dPlugin);
+    app.add_plugins(PipelinesReadyPlugin);
+
     app.add_plugins(FrameTimeDiagnosticsPlugin::default());
-    app.insert_resource(PkvStore::new("beritens", "grumpy_sword"));
+    app.insert_resource(open_save_store());
     #[cfg(debug_assertions)] // debug/dev builds only
     {
         use bevy::diagnostic::LogDiagnosticsPlugin;
         app.add_plugins(LogDiagnosticsPlugin::default());
     }
     app.insert_resource(ClearColor(Color::srgb(0.0, 0.0, 0.0)));
-    // app.add_systems(Update, spawn_level.run_if(in_state(GameState::Menu)));
-    app.run();
+}
+
+//no window, renderer or audio, only what the InGame loop needs
+fn add_headless_plugins(app: &mut App, seconds: f32) {
+    app.add_plugins((
+        MinimalPlugins,
+        LogPlugin::default(),
+        AssetPlugin {
+            meta_check: AssetMetaCheck::Never,
+            ..default()
+        },
+        StatesPlugin,
+        TransformPlugin,
+        HierarchyPlugin,
+        InputPlugin,
+    ));
+    app.add_plugins(HeadlessPlugin { seconds });
+    //keep simulated runs away from the real save
+    app.insert_resource(PkvStore::new("beritens", "grumpy_sword_headless"));
 }
 
 // #[derive(serde::Deserialize)]
@@ -1,23 +1,24 @@
-use crate::combat::{Cause, Dead, Direction};
+use crate::combat::{kill, Cause, CombatSet, Dead, Direction, EntityKilled};
 use crate::effects::AriseCooldownEffect;
 use crate::enemy::Walking;
 use crate::game_state::PauseState;
-use crate::input_manager::{Action, BasicControl};
+use crate::input_manager::GameplayInput;
 use crate::player_states::WalkAnim;
+use crate::skeleton_ai::{RightGroup, SkeletonAi, SkeletonAiSet, SquadOrders};
+use crate::spawning::Enemy;
 use avian2d::collision::CollisionLayers;
 use avian2d::math::PI;
 use avian2d::prelude::{LinearVelocity, PhysicsLayer};
-use bevy::app::{App, Plugin};
+use bevy::app::{App, FixedUpdate, Plugin};
 use bevy::input::gamepad::{GamepadConnection, GamepadEvent};
 use bevy::input::Axis;
 use bevy::prelude::{
-    debug, in_state, info, Children, Commands, Component, Entity, EventReader, Gamepad,
-    GamepadAxis, GamepadButton, IntoSystemConfigs, Quat, Query, Res, Resource, Transform, Update,
-    Vec2, Vec3, With,
+    debug, in_state, info, Children, Commands, Component, Entity, EventReader, EventWriter,
+    Gamepad, GamepadAxis, GamepadButton, Has, IntoSystemConfigs, Quat, Query, Res, Resource,
+    Transform, Update, Vec2, Vec3, With, Without,
 };
 use bevy_firework::core::{ParticleSpawner, ParticleSpawnerData};
 use leafwing_input_manager::clashing_inputs::BasicInputs;
-use leafwing_input_manager::prelude::ActionState;
 use pid::Pid;
 
 pub struct MovementPlugin;
@@ -25,10 +26,18 @@ pub struct MovementPlugin;
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
-            Update,
-            (movement, look_direction_system, barrier_death_system)
+            FixedUpdate,
+            (
+                movement.after(SkeletonAiSet),
+                //after the hits, so something killed by both only dies once
+                barrier_death_system.after(CombatSet),
+            )
                 .run_if(in_state(PauseState::Running)),
         );
+        app.add_systems(
+            Update,
+            look_direction_system.run_if(in_state(PauseState::Running)),
+        );
         // add things to your app here
     }
 }
@@ -57,12 +66,20 @@ pub struct Barrier {
     pub(crate) max: f32,
 }
 fn barrier_death_system(
-    transform_query: Query<(&Transform, &Barrier, Entity)>,
+    transform_query: Query<(&Transform, &Barrier, Entity, Option<&Enemy>), Without<Dead>>,
     mut commands: Commands,
+    mut killed_events: EventWriter<EntityKilled>,
 ) {
-    for (transform, barrier, entity) in transform_query.iter() {
+    for (transform, barrier, entity, enemy) in transform_query.iter() {
         if (transform.translation.x < barrier.min || transform.translation.x > barrier.max) {
-            commands.entity(entity).insert(Dead { reason: Cause::Out });
+            kill(
+                &mut commands,
+                &mut killed_events,
+                entity,
+                Cause::Out,
+                enemy,
+                transform,
+            );
         }
         // transform.translation.x = transform.translation.x.clamp(barrier.min, barrier.max)
     }
@@ -93,25 +110,40 @@ impl Default for FancyWalk {
 }
 fn movement(
     mut commands: Commands,
-    input_query: Query<(&ActionState<Action>), With<BasicControl>>,
+    input_query: Query<(&GameplayInput)>,
+    orders: Option<Res<SquadOrders>>,
     mut rb_query: Query<(
         &mut LinearVelocity,
         &mut FancyWalk,
         &Controllable,
         &Transform,
+        Option<&SkeletonAi>,
+        Option<&mut WalkAnim>,
+        Has<RightGroup>,
     )>,
-    mut walk_query: Query<&mut WalkAnim, With<Controllable>>,
     mut arise_cooldown_effect_query: Query<(&mut ParticleSpawner), With<AriseCooldownEffect>>,
 ) {
     let mut x_input = 0.0;
-    for (action) in &input_query {
+    let mut right_input = 0.0;
+    for (input) in &input_query {
         // let x = gamepad.get(GamepadAxis::LeftStickX).unwrap();
-        let x = action.clamped_value(&Action::Move);
+        let x = input.movement;
         if x.abs() > 0.01 {
             x_input = x;
         }
+        if input.right_movement.abs() > 0.01 {
+            right_input = input.right_movement;
+        }
     }
-    for (mut linear_velocity, mut fancy_walk, controllable, transform) in rb_query.iter_mut() {
+    let autonomous = orders.as_ref().map_or(false, |orders| orders.autonomous);
+    for (mut linear_velocity, mut fancy_walk, controllable, transform, ai, walk, right_group) in
+        rb_query.iter_mut()
+    {
+        let walk_input = match (ai, &orders) {
+            (Some(ai), _) if autonomous => ai.movement,
+            (_, Some(orders)) => orders.group_movement(x_input, right_input, right_group),
+            _ => x_input,
+        };
         fancy_walk.pid_control.d_limit = if transform.translation.y > 0.0 {
             0.0
         } else {
@@ -125,15 +157,15 @@ fn movement(
         };
         fancy_walk
             .pid_control
-            .setpoint(x_input * controllable.speed);
+            .setpoint(walk_input * controllable.speed);
         let output = fancy_walk
             .pid_control
             .next_control_output(linear_velocity.x)
             .output;
         linear_velocity.x += output;
-    }
-    for (mut walk) in walk_query.iter_mut() {
-        walk.active = x_input.abs() > 0.01;
+        if let Some(mut walk) = walk {
+            walk.active = walk_input.abs() > 0.01;
+        }
     }
     for (mut effect) in arise_cooldown_effect_query.iter_mut() {
         if (signum_with_zero_handling(effect.acceleration.x) != signum_with_zero_handling(x_input))
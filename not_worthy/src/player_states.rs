@@ -1,11 +1,11 @@
 use crate::animation::AnimationManager;
 use crate::asset_load::PlayerSounds;
-use crate::combat::{Cause, Dead};
+use crate::combat::{Cause, Dead, EntityKilled};
 use crate::game_state::GameState;
-use bevy::app::{App, Plugin, PreUpdate};
+use bevy::app::{App, FixedPreUpdate, FixedUpdate, Plugin};
 use bevy::audio::{AudioPlayer, PlaybackMode};
 use bevy::prelude::{
-    in_state, Commands, Component, DespawnRecursiveExt, Entity, IntoSystemConfigs,
+    in_state, Commands, Component, DespawnRecursiveExt, Entity, EventReader, IntoSystemConfigs,
     PlaybackSettings, Query, Res, SystemSet, Time, Timer,
 };
 use bevy::time::TimerMode;
@@ -20,7 +20,7 @@ pub struct PlayerSet;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
-            PreUpdate,
+            FixedPreUpdate,
             (
                 (
                     player_idle_state_system,
@@ -33,6 +33,10 @@ impl Plugin for PlayerPlugin {
                     .run_if(in_state(GameState::InGame)),
             ),
         );
+        app.add_systems(
+            FixedUpdate,
+            player_scream_system.run_if(in_state(GameState::InGame)),
+        );
         // app.add_systems(
         //     PreUpdate,
         //     ((().in_set(PlayerSet),).run_if(in_state(GameState::Main)),),
@@ -245,24 +249,30 @@ fn player_walking_on_exit(mut commands: &mut Commands, entity: Entity) {
 fn player_dead_state_system(
     mut commands: Commands,
     mut active_state_query: Query<(&mut PlayerDeadState, Entity)>,
-    mut dead_query: Query<(&Dead)>,
-    player_sounds: Res<PlayerSounds>,
 ) {
     for (mut state, entity) in active_state_query.iter_mut() {
         commands.entity(entity).despawn_recursive();
-        if let Ok(dead) = dead_query.get(entity) {
-            match dead.reason {
-                Cause::Out => {
-                    commands.spawn((
-                        AudioPlayer::new(player_sounds.scream.clone()),
-                        PlaybackSettings {
-                            mode: PlaybackMode::Despawn,
-                            ..default()
-                        },
-                    ));
-                }
-                _ => {}
-            }
+    }
+}
+
+//skeletons thrown off the edge scream
+fn player_scream_system(
+    mut commands: Commands,
+    mut killed_events: EventReader<EntityKilled>,
+    player_sounds: Option<Res<PlayerSounds>>,
+) {
+    let Some(player_sounds) = player_sounds else {
+        return;
+    };
+    for killed in killed_events.read() {
+        if (killed.cause == Cause::Out && killed.enemy_type.is_none()) {
+            commands.spawn((
+                AudioPlayer::new(player_sounds.scream.clone()),
+                PlaybackSettings {
+                    mode: PlaybackMode::Despawn,
+                    ..default()
+                },
+            ));
         }
     }
 }
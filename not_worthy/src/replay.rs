@@ -0,0 +1,363 @@
+use crate::game_rng::GameRng;
+use crate::game_state::GameState;
+use crate::headless::{headless_time_strategy, Headless};
+use crate::input_manager::{Action, BasicControl};
+use crate::level_loading::SelectedLevel;
+use crate::state_handling::{load_save_data, store_save_data, SaveData};
+use bevy::app::{App, AppExit, Last, Plugin, PreUpdate, Startup};
+use bevy::log::{info, warn};
+use bevy::prelude::{
+    in_state, Commands, Entity, EventReader, IntoSystemConfigs, NextState, OnEnter, OnExit, Query,
+    Res, ResMut, Resource, State, Time, With,
+};
+use bevy::time::TimeUpdateStrategy;
+use bevy_pkv::PkvStore;
+use leafwing_input_manager::plugin::InputManagerSystem;
+use leafwing_input_manager::prelude::{ActionState, InputMap};
+use std::path::PathBuf;
+use std::time::Duration;
+
+//bump when the layout of Replay changes, old replays are refused
+pub const REPLAY_VERSION: u32 = 1;
+
+//one frame of the InGame loop
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Default)]
+pub struct ReplayFrame {
+    //exact frame time, so the timers tick the same way on playback
+    pub delta_nanos: u64,
+    pub movement: f32,
+    pub punch: bool,
+    pub special: bool,
+    //missing in replays from before the command button
+    #[serde(default)]
+    pub command: bool,
+    #[serde(default)]
+    pub right_movement: f32,
+    #[serde(default)]
+    pub split: bool,
+    #[serde(default)]
+    pub cycle_targeting: bool,
+}
+
+//everything a run depends on besides the game data
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub level: usize,
+    pub save: SaveData,
+    pub frames: Vec<ReplayFrame>,
+}
+
+pub enum ReplayMode {
+    Record(PathBuf),
+    Playback(Replay),
+}
+
+//reads `--record <file>` or `--replay <file>` from the command line
+pub fn replay_mode_from_args() -> Option<ReplayMode> {
+    let args: Vec<String> = std::env::args().collect();
+    for (index, arg) in args.iter().enumerate() {
+        let Some(path) = args.get(index + 1) else {
+            continue;
+        };
+        match arg.as_str() {
+            "--record" => return Some(ReplayMode::Record(PathBuf::from(path))),
+            "--replay" => match load_replay(&PathBuf::from(path)) {
+                Ok(replay) => return Some(ReplayMode::Playback(replay)),
+                Err(err) => {
+                    //logging isn't set up yet
+                    eprintln!("{}, playing without replay", err);
+                    return None;
+                }
+            },
+            _ => {}
+        }
+    }
+    return None;
+}
+
+fn load_replay(path: &PathBuf) -> Result<Replay, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|err| format!("could not read replay {}: {}", path.display(), err))?;
+    let replay: Replay = serde_json::from_str(&json)
+        .map_err(|err| format!("{} is not a replay: {}", path.display(), err))?;
+    if (replay.version != REPLAY_VERSION) {
+        return Err(format!(
+            "replay version {} does not match {}",
+            replay.version, REPLAY_VERSION
+        ));
+    }
+    return Ok(replay);
+}
+
+impl ReplayMode {
+    //the run seed has to come from the replay when playing one back
+    pub fn seed(&self) -> Option<u64> {
+        match self {
+            ReplayMode::Record(_) => None,
+            ReplayMode::Playback(replay) => Some(replay.seed),
+        }
+    }
+}
+
+pub struct ReplayPlugin {
+    pub mode: ReplayMode,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        match &self.mode {
+            ReplayMode::Record(path) => {
+                app.insert_resource(ReplayRecorder {
+                    path: path.clone(),
+                    replay: Replay::default(),
+                });
+                app.add_systems(OnEnter(GameState::InGame), start_recording_system);
+                app.add_systems(
+                    Last,
+                    (
+                        record_frame_system.run_if(in_state(GameState::InGame)),
+                        write_on_exit_system,
+                    )
+                        .chain(),
+                );
+                app.add_systems(OnExit(GameState::InGame), write_recording_system);
+            }
+            ReplayMode::Playback(replay) => {
+                //the recorded upgrades must not touch the real profiles
+                //added after the other plugins, so this store replaces theirs
+                app.insert_resource(PkvStore::new("beritens", "grumpy_sword_replay"));
+                app.insert_resource(ReplayPlayback {
+                    replay: replay.clone(),
+                    frame: 0,
+                    input_map: None,
+                    finished: false,
+                });
+                app.add_systems(Startup, setup_playback_system);
+                app.add_systems(OnEnter(GameState::InGame), take_input_map_system);
+                app.add_systems(
+                    PreUpdate,
+                    playback_input_system
+                        .in_set(InputManagerSystem::ManualControl)
+                        .run_if(run_starting_or_running),
+                );
+                app.add_systems(
+                    Last,
+                    advance_playback_system.run_if(in_state(GameState::InGame)),
+                );
+            }
+        }
+    }
+}
+
+//recording
+
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    path: PathBuf,
+    replay: Replay,
+}
+
+fn start_recording_system(
+    mut recorder: ResMut<ReplayRecorder>,
+    mut pkv: ResMut<PkvStore>,
+    selected_level: Res<SelectedLevel>,
+) {
+    recorder.replay = Replay {
+        version: REPLAY_VERSION,
+        seed: 0,
+        level: selected_level.index,
+        save: load_save_data(&mut pkv),
+        frames: Vec::new(),
+    };
+}
+
+fn record_frame_system(
+    time: Res<Time>,
+    mut recorder: ResMut<ReplayRecorder>,
+    input_query: Query<&ActionState<Action>, With<BasicControl>>,
+) {
+    let mut frame = ReplayFrame {
+        delta_nanos: time.delta().as_nanos() as u64,
+        ..Default::default()
+    };
+    if let Ok(action_state) = input_query.get_single() {
+        frame.movement = action_state.clamped_value(&Action::Move);
+        frame.punch = action_state.pressed(&Action::Punch);
+        frame.special = action_state.pressed(&Action::Special);
+        frame.command = action_state.pressed(&Action::Command);
+        frame.right_movement = action_state.clamped_value(&Action::MoveRight);
+        frame.split = action_state.pressed(&Action::Split);
+        frame.cycle_targeting = action_state.pressed(&Action::CycleTargeting);
+    }
+    recorder.replay.frames.push(frame);
+}
+
+fn write_recording(recorder: &mut ReplayRecorder, seed: u64) {
+    if (recorder.replay.frames.is_empty()) {
+        return;
+    }
+    //the seed is picked when the run starts, it doesn't change during the run
+    recorder.replay.seed = seed;
+    match serde_json::to_string(&recorder.replay) {
+        Ok(json) => match std::fs::write(&recorder.path, json) {
+            Ok(()) => info!(
+                "recorded {} frames to {}",
+                recorder.replay.frames.len(),
+                recorder.path.display()
+            ),
+            Err(err) => warn!("could not write {}: {}", recorder.path.display(), err),
+        },
+        Err(err) => warn!("could not serialize replay: {}", err),
+    }
+    recorder.replay.frames.clear();
+}
+
+//every run overwrites the file, so it always holds the last one
+fn write_recording_system(mut recorder: ResMut<ReplayRecorder>, game_rng: Res<GameRng>) {
+    write_recording(&mut recorder, game_rng.seed);
+}
+
+//closing the game mid run still keeps what was recorded
+fn write_on_exit_system(
+    mut exit_events: EventReader<AppExit>,
+    mut recorder: ResMut<ReplayRecorder>,
+    game_rng: Res<GameRng>,
+) {
+    if (exit_events.read().next().is_some()) {
+        write_recording(&mut recorder, game_rng.seed);
+    }
+}
+
+//playback
+
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    frame: usize,
+    //real input is ignored while the replay plays
+    input_map: Option<InputMap<Action>>,
+    finished: bool,
+}
+
+fn frame_duration(frame: &ReplayFrame) -> TimeUpdateStrategy {
+    return TimeUpdateStrategy::ManualDuration(Duration::from_nanos(frame.delta_nanos));
+}
+
+fn setup_playback_system(
+    mut commands: Commands,
+    playback: Res<ReplayPlayback>,
+    mut pkv: ResMut<PkvStore>,
+    mut selected_level: ResMut<SelectedLevel>,
+) {
+    store_save_data(&mut pkv, &playback.replay.save);
+    selected_level.index = playback.replay.level;
+    //the first frame of the run has to use the recorded time already
+    if let Some(frame) = playback.replay.frames.first() {
+        commands.insert_resource(frame_duration(frame));
+    }
+    info!(
+        "playing back {} frames with seed {}",
+        playback.replay.frames.len(),
+        playback.replay.seed
+    );
+}
+
+//the input of the first InGame frame is read before the state actually changes
+fn run_starting_or_running(
+    state: Res<State<GameState>>,
+    next_state: Res<NextState<GameState>>,
+) -> bool {
+    if (*state.get() == GameState::InGame) {
+        return true;
+    }
+    return matches!(*next_state, NextState::Pending(GameState::InGame));
+}
+
+fn take_input_map_system(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    input_query: Query<(Entity, &InputMap<Action>), With<BasicControl>>,
+) {
+    if (playback.finished) {
+        return;
+    }
+    for (entity, input_map) in input_query.iter() {
+        playback.input_map = Some(input_map.clone());
+        commands.entity(entity).remove::<InputMap<Action>>();
+    }
+}
+
+fn playback_input_system(
+    playback: Res<ReplayPlayback>,
+    mut input_query: Query<&mut ActionState<Action>, With<BasicControl>>,
+) {
+    if (playback.finished) {
+        return;
+    }
+    let frame = playback
+        .replay
+        .frames
+        .get(playback.frame)
+        .copied()
+        .unwrap_or_default();
+    for mut action_state in input_query.iter_mut() {
+        action_state.set_axis_value(&Action::Move, frame.movement);
+        action_state.set_axis_value(&Action::MoveRight, frame.right_movement);
+        if (frame.punch) {
+            action_state.press(&Action::Punch);
+        } else {
+            action_state.release(&Action::Punch);
+        }
+        if (frame.special) {
+            action_state.press(&Action::Special);
+        } else {
+            action_state.release(&Action::Special);
+        }
+        if (frame.command) {
+            action_state.press(&Action::Command);
+        } else {
+            action_state.release(&Action::Command);
+        }
+        if (frame.split) {
+            action_state.press(&Action::Split);
+        } else {
+            action_state.release(&Action::Split);
+        }
+        if (frame.cycle_targeting) {
+            action_state.press(&Action::CycleTargeting);
+        } else {
+            action_state.release(&Action::CycleTargeting);
+        }
+    }
+}
+
+fn advance_playback_system(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    input_query: Query<Entity, With<BasicControl>>,
+    headless: Option<Res<Headless>>,
+) {
+    if (playback.finished) {
+        return;
+    }
+    playback.frame += 1;
+    if let Some(frame) = playback.replay.frames.get(playback.frame) {
+        commands.insert_resource(frame_duration(frame));
+        return;
+    }
+
+    //out of frames, hand control back
+    info!("replay finished after {} frames", playback.frame);
+    playback.finished = true;
+    match headless {
+        Some(_) => commands.insert_resource(headless_time_strategy()),
+        None => commands.insert_resource(TimeUpdateStrategy::Automatic),
+    }
+    if let Some(input_map) = playback.input_map.take() {
+        for entity in input_query.iter() {
+            commands.entity(entity).insert(input_map.clone());
+        }
+    }
+}
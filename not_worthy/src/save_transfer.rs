@@ -0,0 +1,181 @@
+use crate::asset_load::{EnemyInfos, GameData, GameInfos};
+use crate::game_state::GameState;
+use crate::headless::LOADING_TIMEOUT;
+use crate::state_handling::{
+    load_profiles, load_save_data, open_save_store, store_save_data, SaveData, Upgrade,
+    SAVE_VERSION,
+};
+use bevy::app::{App, AppExit, Update};
+use bevy::asset::{AssetMetaCheck, AssetPlugin, Assets};
+use bevy::log::{error, info, LogPlugin};
+use bevy::prelude::{
+    in_state, EventWriter, IntoSystemConfigs, MinimalPlugins, OnEnter, Real, Res, ResMut, Resource,
+    Time,
+};
+use bevy::state::app::{AppExtStates, StatesPlugin};
+use bevy_asset_loader::prelude::{ConfigureLoadingState, LoadingState, LoadingStateAppExt};
+use bevy_common_assets::json::JsonAssetPlugin;
+use bevy_pkv::PkvStore;
+use std::path::PathBuf;
+
+//dump or restore the current profile, used to hand progression states around
+#[derive(Resource, Clone, Debug)]
+pub enum SaveTransfer {
+    Export(PathBuf),
+    Import(PathBuf),
+}
+
+//reads `--export-save <file>` or `--import-save <file>` from the command line
+pub fn save_transfer_from_args() -> Option<SaveTransfer> {
+    let args: Vec<String> = std::env::args().collect();
+    for (index, arg) in args.iter().enumerate() {
+        let Some(path) = args.get(index + 1) else {
+            continue;
+        };
+        match arg.as_str() {
+            "--export-save" => return Some(SaveTransfer::Export(PathBuf::from(path))),
+            "--import-save" => return Some(SaveTransfer::Import(PathBuf::from(path))),
+            _ => {}
+        }
+    }
+    return None;
+}
+
+//only loads the game data the save is checked against, then runs the transfer and quits
+pub fn run_save_transfer(transfer: SaveTransfer) -> AppExit {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        LogPlugin::default(),
+        AssetPlugin {
+            meta_check: AssetMetaCheck::Never,
+            ..Default::default()
+        },
+        StatesPlugin,
+    ));
+    app.add_plugins(JsonAssetPlugin::<GameInfos>::new(&["data.json"]));
+    app.add_plugins(JsonAssetPlugin::<EnemyInfos>::new(&["enemies.json"]));
+    app.insert_resource(open_save_store());
+    app.insert_resource(transfer);
+    app.init_state::<GameState>();
+    app.add_loading_state(
+        LoadingState::new(GameState::Loading)
+            .continue_to_state(GameState::Menu)
+            .load_collection::<GameData>(),
+    );
+    app.add_systems(OnEnter(GameState::Menu), transfer_system);
+    app.add_systems(
+        Update,
+        loading_timeout_system.run_if(in_state(GameState::Loading)),
+    );
+    return app.run();
+}
+
+//missing or broken game data never finishes loading, give up instead of waiting forever
+fn loading_timeout_system(time: Res<Time<Real>>, mut exit: EventWriter<AppExit>) {
+    if (time.elapsed() > LOADING_TIMEOUT) {
+        error!(
+            "game data did not load within {} seconds, are the assets there?",
+            LOADING_TIMEOUT.as_secs()
+        );
+        exit.send(AppExit::error());
+    }
+}
+
+fn transfer_system(
+    transfer: Res<SaveTransfer>,
+    mut pkv: ResMut<PkvStore>,
+    game_data_res: Res<GameData>,
+    game_datas: Res<Assets<GameInfos>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Some(game_data) = game_datas.get(game_data_res.data.id()) else {
+        error!("game data is missing");
+        exit.send(AppExit::error());
+        return;
+    };
+    let result = match &*transfer {
+        SaveTransfer::Export(path) => export_save(&mut pkv, path),
+        SaveTransfer::Import(path) => import_save(&mut pkv, path, game_data),
+    };
+    match result {
+        Ok(()) => {
+            exit.send(AppExit::Success);
+        }
+        Err(err) => {
+            error!("{}", err);
+            exit.send(AppExit::error());
+        }
+    }
+}
+
+fn export_save(pkv: &mut PkvStore, path: &PathBuf) -> Result<(), String> {
+    let profile = load_profiles(pkv).current;
+    let save = load_save_data(pkv);
+    let json = serde_json::to_string_pretty(&save)
+        .map_err(|err| format!("could not serialize save data: {}", err))?;
+    std::fs::write(path, json)
+        .map_err(|err| format!("could not write {}: {}", path.display(), err))?;
+    info!("exported profile {} to {}", profile, path.display());
+    return Ok(());
+}
+
+fn import_save(pkv: &mut PkvStore, path: &PathBuf, game_data: &GameInfos) -> Result<(), String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+    let mut save: SaveData = serde_json::from_str(&json)
+        .map_err(|err| format!("{} is not a save file: {}", path.display(), err))?;
+    validate_save(&save, game_data)?;
+    save.version = SAVE_VERSION;
+
+    let profile = load_profiles(pkv).current;
+    store_save_data(pkv, &save);
+    info!("imported {} into profile {}", path.display(), profile);
+    return Ok(());
+}
+
+//number of levels the game data has for an upgrade, the shop and the stats both index with it
+fn level_count(game_data: &GameInfos, upgrade: Upgrade) -> usize {
+    let values = match upgrade {
+        Upgrade::Knockback => game_data.knockback.len(),
+        Upgrade::Damage => game_data.damage.len(),
+        Upgrade::Speed => game_data.speed.len(),
+        Upgrade::AriseCooldown => game_data.arise_cooldown.len(),
+        Upgrade::AriseCount => game_data.arise_count.len(),
+        Upgrade::AttackCooldown => game_data.attack_cooldown.len(),
+        Upgrade::CritChance => game_data.crit_chance.len(),
+        Upgrade::CritMultiplier => game_data.crit_multiplier.len(),
+        Upgrade::CorpseDecay => game_data.corpse_decay.len(),
+        Upgrade::SwordDurability => game_data.sword_health.len(),
+        Upgrade::SwordRepair => game_data.sword_repair.len(),
+    };
+    let shop_displays = game_data
+        .shop_items
+        .get(upgrade.shop_item())
+        .map(|shop_item| shop_item.shop_displays.len())
+        .unwrap_or(0);
+    return values.min(shop_displays);
+}
+
+fn validate_save(save: &SaveData, game_data: &GameInfos) -> Result<(), String> {
+    if (save.version > SAVE_VERSION) {
+        return Err(format!(
+            "save version {} is newer than {}",
+            save.version, SAVE_VERSION
+        ));
+    }
+    if (save.score < 0) {
+        return Err(format!("score {} is negative", save.score));
+    }
+    for upgrade in Upgrade::ALL.iter() {
+        let level = save.level(*upgrade);
+        let count = level_count(game_data, *upgrade);
+        if (level < 0 || level as usize >= count) {
+            return Err(format!(
+                "{:?} level {} is out of range, the game data has {} levels",
+                upgrade, level, count
+            ));
+        }
+    }
+    return Ok(());
+}
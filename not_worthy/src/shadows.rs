@@ -1,5 +1,5 @@
 use crate::game_state::GameState;
-use crate::spawning::{spawn_enemy_system, spawn_fast_enemy_system};
+use crate::spawning::spawn_enemy_system;
 use bevy::app::{App, Plugin, Update};
 use bevy::prelude::{
     in_state, Commands, Component, Entity, GlobalTransform, IntoSystemConfigs, Query, Transform,
@@ -0,0 +1,200 @@
+use crate::combat::{CombatSet, Dead, Direction, Hitter, Hitting, PlayerCombatSettings};
+use crate::enemy::SWORD_POSITION;
+use crate::game_state::{GameState, PauseState};
+use crate::hit_detection::{Faction, HitDetection};
+use crate::input_manager::GameplayInput;
+use crate::player_states::AttackNow;
+use crate::spawning::Enemy;
+use crate::summoning::Player;
+use bevy::app::{App, FixedUpdate, Plugin};
+use bevy::log::info;
+use bevy::prelude::{
+    in_state, Added, Commands, Component, Entity, IntoSystemConfigs, OnEnter, Query, Res, ResMut,
+    Resource, SystemSet, Time, Timer, Transform, With, Without,
+};
+use bevy::time::TimerMode;
+use std::time::Duration;
+
+//how far an autonomous skeleton looks for heroes
+const AI_AGGRO_RADIUS: f32 = 8.0;
+//how long a rally order keeps the skeletons at the sword
+const RALLY_TIME: f32 = 2.0;
+//close enough to the rally point to stand still
+const RALLY_DISTANCE: f32 = 0.5;
+
+pub struct SkeletonAiPlugin;
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SkeletonAiSet;
+
+impl Plugin for SkeletonAiPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SquadOrders::default());
+        app.add_systems(OnEnter(GameState::InGame), reset_orders_system);
+        app.add_systems(
+            FixedUpdate,
+            (squad_orders_system, group_raised_system, skeleton_ai_system)
+                .chain()
+                .in_set(SkeletonAiSet)
+                .before(CombatSet)
+                .run_if(in_state(GameState::InGame))
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+//what the sword tells the skeletons
+//commanded skeletons all follow the input, autonomous ones pick their own fights
+#[derive(Resource)]
+pub struct SquadOrders {
+    pub autonomous: bool,
+    //left and right of the sword move and face on their own
+    pub split: bool,
+    //running while the skeletons fall back to the sword
+    pub rally: Timer,
+}
+
+impl Default for SquadOrders {
+    fn default() -> Self {
+        let mut rally = Timer::from_seconds(RALLY_TIME, TimerMode::Once);
+        rally.set_elapsed(Duration::from_secs_f32(RALLY_TIME));
+        return SquadOrders {
+            autonomous: false,
+            split: false,
+            rally,
+        };
+    }
+}
+
+//skeletons steered by the right move axis while split
+//set once when the squad splits or the skeleton is raised, so walking past the sword doesn't swap groups
+#[derive(Component)]
+pub struct RightGroup;
+
+#[derive(Component, Default)]
+pub struct SkeletonAi {
+    //used by movement instead of the input while autonomous
+    pub movement: f32,
+    pub cooldown: Timer,
+}
+
+impl SquadOrders {
+    //move axis of the group the skeleton belongs to
+    pub fn group_movement(&self, movement: f32, right_movement: f32, right_group: bool) -> f32 {
+        if (self.split && right_group) {
+            return right_movement;
+        }
+        return movement;
+    }
+}
+
+fn reset_orders_system(mut orders: ResMut<SquadOrders>) {
+    *orders = SquadOrders::default();
+}
+
+fn set_group(commands: &mut Commands, entity: Entity, x: f32) {
+    if (x >= SWORD_POSITION.x) {
+        commands.entity(entity).insert(RightGroup);
+    } else {
+        commands.entity(entity).remove::<RightGroup>();
+    }
+}
+
+fn squad_orders_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut orders: ResMut<SquadOrders>,
+    input_query: Query<&GameplayInput>,
+    skeleton_query: Query<(&Transform, Entity), (With<Player>, Without<Dead>)>,
+) {
+    orders.rally.tick(time.delta());
+    for input in input_query.iter() {
+        if (input.command) {
+            orders.autonomous = !orders.autonomous;
+            info!("skeletons autonomous: {}", orders.autonomous);
+        }
+        if (input.split) {
+            orders.split = !orders.split;
+            info!("squad split: {}", orders.split);
+            if (orders.split) {
+                for (transform, entity) in skeleton_query.iter() {
+                    set_group(&mut commands, entity, transform.translation.x);
+                }
+            }
+        }
+        //while autonomous the attack button calls everyone back
+        if (orders.autonomous && input.punch) {
+            orders.rally.reset();
+        }
+    }
+}
+
+//skeletons raised while split join the side of the sword they come up on
+fn group_raised_system(
+    mut commands: Commands,
+    orders: Res<SquadOrders>,
+    raised_query: Query<(&Transform, Entity), Added<Player>>,
+) {
+    if (!orders.split) {
+        return;
+    }
+    for (transform, entity) in raised_query.iter() {
+        set_group(&mut commands, entity, transform.translation.x);
+    }
+}
+
+fn skeleton_ai_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    orders: Res<SquadOrders>,
+    hit_detection: Res<HitDetection>,
+    combat_settings_query: Query<&PlayerCombatSettings>,
+    mut skeleton_query: Query<
+        (&Transform, &mut Direction, &Hitter, &mut SkeletonAi, Entity),
+        (With<Player>, Without<Dead>),
+    >,
+    hero_query: Query<&Transform, (With<Enemy>, Without<Player>)>,
+) {
+    if (!orders.autonomous) {
+        return;
+    }
+    let attack_cooldown = combat_settings_query
+        .get_single()
+        .map_or(Duration::ZERO, |settings| settings.cooldown.duration());
+    let rallying = !orders.rally.finished();
+
+    for (transform, mut direction, hitter, mut ai, entity) in skeleton_query.iter_mut() {
+        ai.cooldown.tick(time.delta());
+        let x = transform.translation.x;
+
+        if (!rallying) {
+            let hero = hit_detection
+                .nearest(Faction::Hero, x, AI_AGGRO_RADIUS)
+                .and_then(|(hero, distance)| Some((hero_query.get(hero).ok()?, distance)));
+            if let Some((hero_transform, distance)) = hero {
+                let towards = (hero_transform.translation.x - x).signum();
+                let reach = hitter.offset.x + hitter.hit_box.x / 2.0;
+                direction.direction = towards;
+                if (distance > reach) {
+                    ai.movement = towards;
+                    continue;
+                }
+                ai.movement = 0.0;
+                if (ai.cooldown.finished()) {
+                    ai.cooldown.set_duration(attack_cooldown);
+                    ai.cooldown.reset();
+                    commands.entity(entity).insert((Hitting {}, AttackNow {}));
+                }
+                continue;
+            }
+        }
+
+        //nothing to fight, hold the position unless called back
+        ai.movement = 0.0;
+        let to_rally_point = SWORD_POSITION.x - x;
+        if (rallying && to_rally_point.abs() > RALLY_DISTANCE) {
+            ai.movement = to_rally_point.signum();
+            direction.direction = ai.movement;
+        }
+    }
+}
@@ -1,18 +1,483 @@
-use bevy::log::info;
-use bevy::prelude::ResMut;
-use bevy_pkv::PkvStore;
-
-pub fn get_sotred_value(pkv: &mut ResMut<PkvStore>, key: &str) -> i32 {
-    if let Ok(progress) = pkv.get::<String>(key.clone()) {
-        return progress.parse::<i32>().unwrap();
-    } else {
-        pkv.set_string(key, "0")
-            .expect("failed to store game state");
-        info!("new value");
+use bevy::log::{info, warn};
+use bevy::prelude::Resource;
+use bevy_pkv::{GetError, PkvStore};
+
+const SAVE_KEY: &str = "save_data";
+const SAVE_ORGANIZATION: &str = "beritens";
+const SAVE_APPLICATION: &str = "grumpy_sword";
+//bump this and add a step to `migrate` whenever the layout of SaveData changes
+//version 0 is the old layout with every value as its own string key
+pub const SAVE_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Upgrade {
+    Knockback,
+    Damage,
+    Speed,
+    AriseCooldown,
+    AriseCount,
+    AttackCooldown,
+    CritChance,
+    CritMultiplier,
+    CorpseDecay,
+    SwordDurability,
+    SwordRepair,
+}
+
+impl Upgrade {
+    pub const ALL: [Upgrade; 11] = [
+        Upgrade::Knockback,
+        Upgrade::Damage,
+        Upgrade::Speed,
+        Upgrade::AriseCooldown,
+        Upgrade::AriseCount,
+        Upgrade::AttackCooldown,
+        Upgrade::CritChance,
+        Upgrade::CritMultiplier,
+        Upgrade::CorpseDecay,
+        Upgrade::SwordDurability,
+        Upgrade::SwordRepair,
+    ];
+
+    //position of the upgrade in the shop items of the game data
+    pub fn shop_item(&self) -> usize {
+        match self {
+            Upgrade::Knockback => 0,
+            Upgrade::Damage => 1,
+            Upgrade::Speed => 2,
+            Upgrade::AriseCooldown => 3,
+            Upgrade::AriseCount => 4,
+            Upgrade::AttackCooldown => 5,
+            Upgrade::CritChance => 6,
+            Upgrade::CritMultiplier => 7,
+            Upgrade::CorpseDecay => 8,
+            Upgrade::SwordDurability => 9,
+            Upgrade::SwordRepair => 10,
+        }
+    }
+
+    //key the value was stored under before SaveData existed
+    pub fn legacy_key(&self) -> &'static str {
+        match self {
+            Upgrade::Knockback => "knockback",
+            Upgrade::Damage => "damage",
+            Upgrade::Speed => "speed",
+            Upgrade::AriseCooldown => "arise_cooldown",
+            Upgrade::AriseCount => "arise_count",
+            Upgrade::AttackCooldown => "attack_cooldown",
+            //never stored as their own keys, these read as 0
+            Upgrade::CritChance => "crit_chance",
+            Upgrade::CritMultiplier => "crit_multiplier",
+            Upgrade::CorpseDecay => "corpse_decay",
+            Upgrade::SwordDurability => "sword_durability",
+            Upgrade::SwordRepair => "sword_repair",
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct UpgradeLevels {
+    pub knockback: i32,
+    pub damage: i32,
+    pub speed: i32,
+    pub arise_cooldown: i32,
+    pub arise_count: i32,
+    pub attack_cooldown: i32,
+    pub crit_chance: i32,
+    pub crit_multiplier: i32,
+    pub corpse_decay: i32,
+    pub sword_durability: i32,
+    pub sword_repair: i32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SaveData {
+    pub version: u32,
+    pub score: i32,
+    pub upgrades: UpgradeLevels,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        return SaveData {
+            version: SAVE_VERSION,
+            score: 0,
+            upgrades: UpgradeLevels::default(),
+        };
+    }
+}
+
+impl SaveData {
+    pub fn level(&self, upgrade: Upgrade) -> i32 {
+        match upgrade {
+            Upgrade::Knockback => self.upgrades.knockback,
+            Upgrade::Damage => self.upgrades.damage,
+            Upgrade::Speed => self.upgrades.speed,
+            Upgrade::AriseCooldown => self.upgrades.arise_cooldown,
+            Upgrade::AriseCount => self.upgrades.arise_count,
+            Upgrade::AttackCooldown => self.upgrades.attack_cooldown,
+            Upgrade::CritChance => self.upgrades.crit_chance,
+            Upgrade::CritMultiplier => self.upgrades.crit_multiplier,
+            Upgrade::CorpseDecay => self.upgrades.corpse_decay,
+            Upgrade::SwordDurability => self.upgrades.sword_durability,
+            Upgrade::SwordRepair => self.upgrades.sword_repair,
+        }
+    }
+
+    pub fn level_mut(&mut self, upgrade: Upgrade) -> &mut i32 {
+        match upgrade {
+            Upgrade::Knockback => &mut self.upgrades.knockback,
+            Upgrade::Damage => &mut self.upgrades.damage,
+            Upgrade::Speed => &mut self.upgrades.speed,
+            Upgrade::AriseCooldown => &mut self.upgrades.arise_cooldown,
+            Upgrade::AriseCount => &mut self.upgrades.arise_count,
+            Upgrade::AttackCooldown => &mut self.upgrades.attack_cooldown,
+            Upgrade::CritChance => &mut self.upgrades.crit_chance,
+            Upgrade::CritMultiplier => &mut self.upgrades.crit_multiplier,
+            Upgrade::CorpseDecay => &mut self.upgrades.corpse_decay,
+            Upgrade::SwordDurability => &mut self.upgrades.sword_durability,
+            Upgrade::SwordRepair => &mut self.upgrades.sword_repair,
+        }
+    }
+
+    fn migrate(mut self) -> Self {
+        //0 -> 1: the values only moved from their own keys into SaveData
+        if (self.version == 0) {
+            self.version = 1;
+        }
+        return self;
+    }
+
+    //resets values no run could have produced
+    fn repair(&mut self) {
+        if (self.score < 0) {
+            warn!("save data: score was {}, reset to 0", self.score);
+            self.score = 0;
+        }
+        for upgrade in Upgrade::ALL {
+            let level = self.level_mut(upgrade);
+            if (*level < 0) {
+                warn!("save data: {:?} level was {}, reset to 0", upgrade, level);
+                *level = 0;
+            }
+        }
+    }
+}
+
+fn legacy_value(pkv: &PkvStore, key: &str) -> i32 {
+    let Ok(value) = pkv.get::<String>(key) else {
         return 0;
+    };
+    return parse_legacy_value(key, &value);
+}
+
+//old values were stored as strings
+fn parse_legacy_value(key: &str, value: &str) -> i32 {
+    match value.parse::<i32>() {
+        Ok(value) => value,
+        Err(_) => {
+            warn!(
+                "save data: old value {:?} for {} is corrupted, reset to 0",
+                value, key
+            );
+            0
+        }
+    }
+}
+
+//reads the save from before it was versioned
+fn load_legacy(pkv: &PkvStore) -> SaveData {
+    let mut save = SaveData {
+        version: 0,
+        score: legacy_value(pkv, "score"),
+        upgrades: UpgradeLevels::default(),
+    };
+    for upgrade in Upgrade::ALL {
+        *save.level_mut(upgrade) = legacy_value(pkv, upgrade.legacy_key());
+    }
+    return save;
+}
+
+pub fn open_save_store() -> PkvStore {
+    return PkvStore::new(SAVE_ORGANIZATION, SAVE_APPLICATION);
+}
+
+//None when nothing is stored under the key yet
+fn read_save(pkv: &mut PkvStore, key: &str) -> Option<SaveData> {
+    let mut save = match pkv.get::<SaveData>(key) {
+        Ok(save) => save,
+        Err(GetError::NotFound) => return None,
+        Err(err) => {
+            warn!("save data is corrupted ({:?}), reset to defaults", err);
+            SaveData::default()
+        }
+    };
+    if (save.version > SAVE_VERSION) {
+        warn!(
+            "save data version {} is newer than {}, reset to defaults",
+            save.version, SAVE_VERSION
+        );
+        save = SaveData::default();
+    }
+    let needs_store = save.version < SAVE_VERSION;
+    save = save.migrate();
+    save.repair();
+    if (needs_store) {
+        write_save(pkv, key, &save);
+    }
+    return Some(save);
+}
+
+fn write_save(pkv: &mut PkvStore, key: &str, save: &SaveData) {
+    pkv.set(key, save).expect("failed to store game state");
+}
+
+//save data of the current profile
+pub fn load_save_data(pkv: &mut PkvStore) -> SaveData {
+    let profiles = load_profiles(pkv);
+    return read_save(pkv, &profile_key(&profiles.current)).unwrap_or_default();
+}
+
+pub fn store_save_data(pkv: &mut PkvStore, save: &SaveData) {
+    let profiles = load_profiles(pkv);
+    write_save(pkv, &profile_key(&profiles.current), save);
+}
+
+//profiles
+
+const PROFILES_KEY: &str = "profiles";
+const DEFAULT_PROFILE: &str = "default";
+pub const MAX_PROFILE_NAME_LENGTH: usize = 16;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct Profiles {
+    //last used profile, picked again on the next start
+    pub current: String,
+    pub names: Vec<String>,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        return Profiles {
+            current: DEFAULT_PROFILE.to_string(),
+            names: vec![DEFAULT_PROFILE.to_string()],
+        };
     }
 }
-pub fn store_value(mut pkv: &mut ResMut<PkvStore>, key: &str, value: i32) {
-    pkv.set(key, &value.to_string())
-        .expect("failed to store game state");
+
+fn profile_key(name: &str) -> String {
+    return format!("{}:{}", SAVE_KEY, name);
+}
+
+pub fn load_profiles(pkv: &mut PkvStore) -> Profiles {
+    let mut profiles = match pkv.get::<Profiles>(PROFILES_KEY) {
+        Ok(profiles) => profiles,
+        Err(GetError::NotFound) => {
+            //the save from before profiles existed becomes the default profile
+            let save = match read_save(pkv, SAVE_KEY) {
+                Some(save) => save,
+                None => {
+                    info!("no versioned save data, migrating old values");
+                    let mut save = load_legacy(pkv).migrate();
+                    save.repair();
+                    save
+                }
+            };
+            let profiles = Profiles::default();
+            write_save(pkv, &profile_key(&profiles.current), &save);
+            store_profiles(pkv, &profiles);
+            return profiles;
+        }
+        Err(err) => {
+            warn!("profile list is corrupted ({:?}), reset to defaults", err);
+            Profiles::default()
+        }
+    };
+    if (profiles.names.is_empty()) {
+        profiles.names.push(DEFAULT_PROFILE.to_string());
+    }
+    if (!profiles.names.contains(&profiles.current)) {
+        profiles.current = profiles.names[0].clone();
+    }
+    return profiles;
+}
+
+fn store_profiles(pkv: &mut PkvStore, profiles: &Profiles) {
+    pkv.set(PROFILES_KEY, profiles)
+        .expect("failed to store profiles");
+}
+
+pub fn is_valid_profile_name(profiles: &Profiles, name: &str) -> bool {
+    return !name.trim().is_empty()
+        && name.chars().count() <= MAX_PROFILE_NAME_LENGTH
+        && !profiles.names.iter().any(|existing| existing == name);
+}
+
+//creates an empty profile and switches to it
+pub fn create_profile(pkv: &mut PkvStore, name: &str) -> bool {
+    let mut profiles = load_profiles(pkv);
+    if (!is_valid_profile_name(&profiles, name)) {
+        return false;
+    }
+    write_save(pkv, &profile_key(name), &SaveData::default());
+    profiles.names.push(name.to_string());
+    profiles.current = name.to_string();
+    store_profiles(pkv, &profiles);
+    return true;
+}
+
+pub fn select_profile(pkv: &mut PkvStore, name: &str) {
+    let mut profiles = load_profiles(pkv);
+    if (profiles.names.iter().any(|existing| existing == name)) {
+        profiles.current = name.to_string();
+        store_profiles(pkv, &profiles);
+    }
+}
+
+pub fn rename_profile(pkv: &mut PkvStore, name: &str, new_name: &str) -> bool {
+    let mut profiles = load_profiles(pkv);
+    if (!is_valid_profile_name(&profiles, new_name)) {
+        return false;
+    }
+    let Some(index) = profiles.names.iter().position(|existing| existing == name) else {
+        return false;
+    };
+    let save = read_save(pkv, &profile_key(name)).unwrap_or_default();
+    write_save(pkv, &profile_key(new_name), &save);
+    remove_save(pkv, name);
+
+    profiles.names[index] = new_name.to_string();
+    if (profiles.current == name) {
+        profiles.current = new_name.to_string();
+    }
+    store_profiles(pkv, &profiles);
+    return true;
+}
+
+//the last profile can't be deleted
+pub fn delete_profile(pkv: &mut PkvStore, name: &str) -> bool {
+    let mut profiles = load_profiles(pkv);
+    if (profiles.names.len() <= 1) {
+        return false;
+    }
+    let Some(index) = profiles.names.iter().position(|existing| existing == name) else {
+        return false;
+    };
+    remove_save(pkv, name);
+    profiles.names.remove(index);
+    if (profiles.current == name) {
+        profiles.current = profiles.names[0].clone();
+    }
+    store_profiles(pkv, &profiles);
+    return true;
+}
+
+fn remove_save(pkv: &mut PkvStore, name: &str) {
+    if let Err(err) = pkv.remove(&profile_key(name)) {
+        warn!("failed to remove save data of profile {}: {:?}", name, err);
+    }
+}
+
+//settings
+
+const SETTINGS_KEY: &str = "settings";
+
+//shared by all profiles
+#[derive(serde::Serialize, serde::Deserialize, Resource, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub damage_numbers: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        return Settings {
+            damage_numbers: true,
+        };
+    }
+}
+
+pub fn load_settings(pkv: &mut PkvStore) -> Settings {
+    match pkv.get::<Settings>(SETTINGS_KEY) {
+        Ok(settings) => settings,
+        Err(GetError::NotFound) => Settings::default(),
+        Err(err) => {
+            warn!("settings are corrupted ({:?}), reset to defaults", err);
+            Settings::default()
+        }
+    }
+}
+
+pub fn store_settings(pkv: &mut PkvStore, settings: &Settings) {
+    pkv.set(SETTINGS_KEY, settings)
+        .expect("failed to store settings");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_values_parse_or_reset() {
+        assert_eq!(parse_legacy_value("score", "42"), 42);
+        assert_eq!(parse_legacy_value("damage", "-3"), -3);
+        assert_eq!(parse_legacy_value("speed", "fast"), 0);
+        assert_eq!(parse_legacy_value("speed", ""), 0);
+    }
+
+    #[test]
+    fn migrate_moves_legacy_save_to_current_version() {
+        let mut save = SaveData {
+            version: 0,
+            score: 120,
+            upgrades: UpgradeLevels::default(),
+        };
+        *save.level_mut(Upgrade::Damage) = 2;
+        *save.level_mut(Upgrade::AriseCount) = 1;
+
+        let save = save.migrate();
+        assert_eq!(save.version, SAVE_VERSION);
+        assert_eq!(save.score, 120);
+        assert_eq!(save.level(Upgrade::Damage), 2);
+        assert_eq!(save.level(Upgrade::AriseCount), 1);
+    }
+
+    #[test]
+    fn every_upgrade_has_its_own_shop_item() {
+        let mut shop_items: Vec<usize> = Upgrade::ALL
+            .iter()
+            .map(|upgrade| upgrade.shop_item())
+            .collect();
+        shop_items.sort();
+        assert_eq!(shop_items, (0..Upgrade::ALL.len()).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn upgrades_missing_from_an_old_save_read_as_zero() {
+        let save: SaveData =
+            serde_json::from_str(r#"{"version":1,"score":5,"upgrades":{"knockback":3}}"#).unwrap();
+        assert_eq!(save.level(Upgrade::Knockback), 3);
+        for upgrade in Upgrade::ALL {
+            if (upgrade != Upgrade::Knockback) {
+                assert_eq!(save.level(upgrade), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn repair_resets_negative_values() {
+        let mut save = SaveData {
+            score: -10,
+            ..SaveData::default()
+        };
+        *save.level_mut(Upgrade::Speed) = -1;
+        *save.level_mut(Upgrade::Damage) = 4;
+
+        save.repair();
+        assert_eq!(save.score, 0);
+        assert_eq!(save.level(Upgrade::Speed), 0);
+        assert_eq!(save.level(Upgrade::Damage), 4);
+    }
 }
@@ -1,12 +1,16 @@
 use crate::animation::{Animation, AnimationManager};
 use crate::asset_load::{
-    EnemySounds, EnemySprite, GameData, GameInfos, ShadowSprite, SkeletonSprite,
+    level_value, EnemyInfos, EnemySounds, EnemySprite, GameData, GameInfos, ShadowSprite,
+    SkeletonInfo, SkeletonSprite,
 };
-use crate::combat::{Dead, Direction, Health, Hitter, Opfer};
+use crate::combat::{Cause, CombatSet, Dead, Direction, EntityKilled, Health, Hitter, Opfer};
 use crate::effects::{AriseCooldownEffect, AriseEffect};
-use crate::enemy::{BacicEnemActiveState, BasicEnemStateMachine, Target, Walker};
+use crate::enemy::{BacicEnemActiveState, BasicEnemStateMachine, Target, Walker, SWORD_POSITION};
+use crate::game_rng::GameRng;
 use crate::game_state::{GameState, PauseState};
-use crate::input_manager::{Action, BasicControl};
+use crate::headless::visuals_enabled;
+use crate::hit_detection::{Faction, HitDetection};
+use crate::input_manager::GameplayInput;
 use crate::level_loading::SceneObject;
 use crate::movement::{
     get_enemy_collision_layers, get_player_collision_layers, Barrier, Controllable, FancyWalk,
@@ -14,21 +18,26 @@ use crate::movement::{
 };
 use crate::player_states::{PlayerIdleState, PlayerStateMaschine, WalkAnim};
 use crate::shadows::Shadow;
+use crate::skeleton_ai::SkeletonAi;
 use crate::spawning::EnemyType;
-use crate::state_handling::get_sotred_value;
+use crate::state_handling::{load_save_data, Upgrade};
 use avian2d::collision::Collider;
 use avian2d::parry::transformation::utils::transform;
 use avian2d::prelude::{
     LayerMask, LockedAxes, MassPropertiesBundle, RigidBody, SpatialQueryFilter,
 };
-use bevy::app::{App, Plugin, Update};
+use bevy::app::{App, FixedUpdate, Plugin, Update};
 use bevy::asset::{Assets, Handle};
+use bevy::color::Alpha;
 use bevy::image::Image;
+use bevy::log::info;
 use bevy::math::{Quat, Vec2, Vec3};
+use bevy::pbr::{MeshMaterial3d, StandardMaterial};
 use bevy::prelude::{
-    default, in_state, AlphaMode, BuildChildren, ChildBuild, Circle, Commands, Component,
-    DespawnRecursiveExt, Entity, IntoSystemConfigs, NextState, OnEnter, PreUpdate, Query, Res,
-    ResMut, TextureAtlasLayout, Time, Timer, Transform, Visibility, With,
+    default, in_state, Added, AlphaMode, BuildChildren, ChildBuild, Circle, Commands, Component,
+    DespawnRecursiveExt, Entity, Event, EventReader, EventWriter, Has, IntoSystemConfigs,
+    NextState, OnEnter, PreUpdate, Query, Res, ResMut, TextureAtlasLayout, Time, Timer, Transform,
+    Visibility, With,
 };
 use bevy::sprite::TextureAtlas;
 use bevy::time::TimerMode;
@@ -36,10 +45,9 @@ use bevy::utils::tracing::Instrument;
 use bevy_firework::core::{ParticleData, ParticleSpawnerData};
 use bevy_pkv::PkvStore;
 use bevy_sprite3d::{Sprite3dBuilder, Sprite3dParams};
-use leafwing_input_manager::action_state::ActionState;
 use rand::Rng;
 use std::cmp::Ordering;
-use std::collections::{BinaryHeap, VecDeque};
+use std::collections::BinaryHeap;
 use std::f32::consts::PI;
 use std::time::Duration;
 
@@ -47,12 +55,34 @@ pub struct SummoningPlugin;
 
 const BARRIER_MIN: f32 = -8.0;
 const BARRIER_MAX: f32 = 8.0;
+//how close a corpse has to lie to a skeleton for the squad targeting
+const SQUAD_RADIUS: f32 = 3.0;
 
 impl Plugin for SummoningPlugin {
     fn build(&self, app: &mut App) {
+        app.add_event::<SkeletonRaised>();
+        app.add_systems(
+            FixedUpdate,
+            (
+                arise_system,
+                spawn_deceased,
+                //the fallen skeleton has to still be there to read what it was raised from
+                leave_corpse_system.after(CombatSet),
+                decay_system,
+            )
+                .run_if(in_state(GameState::InGame)),
+        );
         app.add_systems(
             Update,
-            (arise_system, spawn_deceased, update_effect_system)
+            (
+                update_effect_system,
+                (
+                    attach_player_visuals_system,
+                    attach_deceased_visuals_system,
+                    fade_deceased_system,
+                )
+                    .run_if(visuals_enabled),
+            )
                 .run_if(in_state(GameState::InGame)),
         );
         app.add_systems(
@@ -64,13 +94,86 @@ impl Plugin for SummoningPlugin {
     }
 }
 
+//which corpses arise brings back first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AriseTargeting {
+    #[default]
+    Nearest,
+    Farthest,
+    Left,
+    Right,
+    //only corpses close to a skeleton, so the squad stays together
+    NearSquad,
+    //the corpses that give the best skeletons, closer ones first among equals
+    Strongest,
+}
+
+impl AriseTargeting {
+    pub const ALL: [AriseTargeting; 6] = [
+        AriseTargeting::Nearest,
+        AriseTargeting::Farthest,
+        AriseTargeting::Left,
+        AriseTargeting::Right,
+        AriseTargeting::NearSquad,
+        AriseTargeting::Strongest,
+    ];
+
+    pub fn next(self) -> AriseTargeting {
+        let index = AriseTargeting::ALL
+            .iter()
+            .position(|targeting| *targeting == self)
+            .unwrap_or(0);
+        return AriseTargeting::ALL[(index + 1) % AriseTargeting::ALL.len()];
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AriseTargeting::Nearest => "nearest",
+            AriseTargeting::Farthest => "farthest",
+            AriseTargeting::Left => "left",
+            AriseTargeting::Right => "right",
+            AriseTargeting::NearSquad => "near squad",
+            AriseTargeting::Strongest => "strongest",
+        }
+    }
+
+    //lower goes first, None leaves the corpse lying
+    fn score(
+        self,
+        x: f32,
+        skeleton_info: &SkeletonInfo,
+        hit_detection: &HitDetection,
+    ) -> Option<f32> {
+        let dist = (x - SWORD_POSITION.x).abs();
+        match self {
+            AriseTargeting::Nearest => Some(dist),
+            AriseTargeting::Farthest => Some(-dist),
+            AriseTargeting::Left => (x < SWORD_POSITION.x).then_some(dist),
+            AriseTargeting::Right => (x >= SWORD_POSITION.x).then_some(dist),
+            AriseTargeting::NearSquad => hit_detection
+                .nearest(Faction::Skeleton, x, SQUAD_RADIUS)
+                .map(|(_, distance)| distance),
+            AriseTargeting::Strongest => {
+                let strength = (skeleton_info.health * skeleton_info.damage).max(0.01);
+                Some(dist - strength * (BARRIER_MAX - BARRIER_MIN))
+            }
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct AriseSettings {
+    pub targeting: AriseTargeting,
     cooldown: Timer,
     num: i32,
     knockback: f32,
     damage: f32,
     speed: f32,
+    damage_roll: [f32; 2],
+    crit_chance: f32,
+    crit_multiplier: f32,
+    corpse_decay: f32,
+    max_resurrections: u32,
 }
 
 fn setup_arise_system(
@@ -79,22 +182,44 @@ fn setup_arise_system(
     mut game_datas: ResMut<Assets<GameInfos>>,
     mut pkv: ResMut<PkvStore>,
 ) {
-    let knockback_level = get_sotred_value(&mut pkv, "knockback");
-    let damage_level = get_sotred_value(&mut pkv, "damage");
-    let speed_level = get_sotred_value(&mut pkv, "speed");
-    let arise_cooldown_level = get_sotred_value(&mut pkv, "arise_cooldown");
-    let arise_count_level = get_sotred_value(&mut pkv, "arise_count");
+    let save_data = load_save_data(&mut pkv);
+    let knockback_level = save_data.level(Upgrade::Knockback);
+    let damage_level = save_data.level(Upgrade::Damage);
+    let speed_level = save_data.level(Upgrade::Speed);
+    let arise_cooldown_level = save_data.level(Upgrade::AriseCooldown);
+    let arise_count_level = save_data.level(Upgrade::AriseCount);
+    let crit_chance_level = save_data.level(Upgrade::CritChance);
+    let crit_multiplier_level = save_data.level(Upgrade::CritMultiplier);
+    let corpse_decay_level = save_data.level(Upgrade::CorpseDecay);
     let mut knockback = 0.0;
     let mut damage = 0.0;
     let mut speed = 0.0;
     let mut arise_cooldown = 0.0;
     let mut arise_count = 0;
+    let mut damage_roll = [1.0, 1.0];
+    let mut crit_chance = 0.0;
+    let mut crit_multiplier = 1.0;
+    let mut corpse_decay = DEFAULT_CORPSE_DECAY;
+    let mut max_resurrections = 1;
     if let Some(game_data) = game_datas.get(game_data.data.id()) {
-        knockback = game_data.knockback[knockback_level as usize];
-        damage = game_data.damage[damage_level as usize];
-        speed = game_data.speed[speed_level as usize];
-        arise_cooldown = game_data.arise_cooldown[arise_cooldown_level as usize];
-        arise_count = game_data.arise_count[arise_count_level as usize];
+        knockback = level_value(&game_data.knockback, knockback_level, knockback);
+        damage = level_value(&game_data.damage, damage_level, damage);
+        speed = level_value(&game_data.speed, speed_level, speed);
+        arise_cooldown = level_value(
+            &game_data.arise_cooldown,
+            arise_cooldown_level,
+            arise_cooldown,
+        );
+        arise_count = level_value(&game_data.arise_count, arise_count_level, arise_count);
+        damage_roll = game_data.damage_roll;
+        crit_chance = level_value(&game_data.crit_chance, crit_chance_level, crit_chance);
+        crit_multiplier = level_value(
+            &game_data.crit_multiplier,
+            crit_multiplier_level,
+            crit_multiplier,
+        );
+        corpse_decay = level_value(&game_data.corpse_decay, corpse_decay_level, corpse_decay);
+        max_resurrections = game_data.max_resurrections;
     }
 
     let mut cooldown = Timer::new(Duration::from_secs_f32(arise_cooldown), TimerMode::Once);
@@ -102,11 +227,17 @@ fn setup_arise_system(
     commands.spawn((
         SceneObject,
         AriseSettings {
+            targeting: AriseTargeting::default(),
             num: arise_count,
             cooldown,
             knockback,
             damage,
             speed,
+            damage_roll,
+            crit_chance,
+            crit_multiplier,
+            corpse_decay,
+            max_resurrections,
         },
     ));
 }
@@ -139,7 +270,28 @@ impl Ord for EntDist {
 struct PlayerSettings {
     knockback: f32,
     speed: f32,
-    damage: f32,
+    min_damage: f32,
+    max_damage: f32,
+    crit_chance: f32,
+    crit_multiplier: f32,
+    health: f32,
+    scale: f32,
+}
+
+impl PlayerSettings {
+    //the upgraded stats adjusted to what the corpse was
+    fn for_corpse(&self, skeleton: &SkeletonInfo) -> PlayerSettings {
+        return PlayerSettings {
+            knockback: self.knockback * skeleton.knockback,
+            speed: self.speed * skeleton.speed,
+            min_damage: self.min_damage * skeleton.damage,
+            max_damage: self.max_damage * skeleton.damage,
+            crit_chance: self.crit_chance,
+            crit_multiplier: self.crit_multiplier,
+            health: skeleton.health,
+            scale: skeleton.scale,
+        };
+    }
 }
 
 fn update_effect_system(
@@ -156,26 +308,32 @@ fn update_effect_system(
 fn arise_system(
     time: Res<Time>,
     mut commands: Commands,
-    input_query: Query<(&ActionState<Action>), With<BasicControl>>,
-    deceased_query: Query<(Entity, &Transform), With<Deceased>>,
-    skelet_asset: Res<SkeletonSprite>,
-    shadow_asset: Res<ShadowSprite>,
-    mut sprite_params: Sprite3dParams,
+    input_query: Query<(&GameplayInput)>,
+    deceased_query: Query<(Entity, &Transform, &Deceased)>,
     mut arise_settings_query: Query<(&mut AriseSettings)>,
     mut arise_effect_query: Query<(&mut ParticleSpawnerData), With<AriseEffect>>,
     mut paused_state: ResMut<NextState<PauseState>>,
+    mut game_rng: ResMut<GameRng>,
+    mut raised_events: EventWriter<SkeletonRaised>,
+    game_data: Res<GameData>,
+    enemy_infos: Res<Assets<EnemyInfos>>,
+    hit_detection: Res<HitDetection>,
 ) {
+    let Ok(mut arise_settings) = arise_settings_query.get_single_mut() else {
+        return;
+    };
+
     let mut summon = false;
-    for (action) in &input_query {
-        if (action.just_pressed(&Action::Special)) {
+    for (input) in &input_query {
+        if (input.special) {
             summon = true;
         }
+        if (input.cycle_targeting) {
+            arise_settings.targeting = arise_settings.targeting.next();
+            info!("arise targeting: {}", arise_settings.targeting.label());
+        }
     }
 
-    let Ok(mut arise_settings) = arise_settings_query.get_single_mut() else {
-        return;
-    };
-
     arise_settings.cooldown.tick(time.delta());
     if (arise_settings.cooldown.finished()) {
         if (summon) {
@@ -192,36 +350,65 @@ fn arise_system(
         let player_settings = PlayerSettings {
             knockback: arise_settings.knockback,
             speed: arise_settings.speed,
-            damage: arise_settings.damage,
+            min_damage: arise_settings.damage * arise_settings.damage_roll[0],
+            max_damage: arise_settings.damage * arise_settings.damage_roll[1],
+            crit_chance: arise_settings.crit_chance,
+            crit_multiplier: arise_settings.crit_multiplier,
+            health: 1.0,
+            scale: 1.0,
+        };
+        let enemy_infos = enemy_infos.get(game_data.enemies.id());
+        let skeleton_info_for = |deceased: &Deceased| {
+            enemy_infos
+                .and_then(|infos| infos.archetypes.get(&deceased.enemy_type.0))
+                .map_or(SkeletonInfo::default(), |archetype| {
+                    archetype.skeleton.clone()
+                })
         };
         let mut max_heap = BinaryHeap::new();
-        for (entity, transform) in deceased_query.iter() {
-            let entdist = EntDist {
-                entity,
-                dist: transform.translation.x.abs(),
+        for (entity, transform, deceased) in deceased_query.iter() {
+            let x = transform.translation.x;
+            //corpses behind the barriers stay where they are instead of taking a slot
+            if (x <= BARRIER_MIN || x >= BARRIER_MAX) {
+                continue;
+            }
+            let Some(score) =
+                arise_settings
+                    .targeting
+                    .score(x, &skeleton_info_for(deceased), &hit_detection)
+            else {
+                continue;
             };
-            max_heap.push(entdist);
+            max_heap.push(EntDist {
+                entity,
+                dist: score,
+            });
             if (max_heap.len() > arise_settings.num as usize) {
                 max_heap.pop();
             }
         }
         for x in &max_heap {
-            if let Ok((entity, transform)) = deceased_query.get(x.entity) {
-                if (transform.translation.x > BARRIER_MIN && transform.translation.x < BARRIER_MAX)
-                {
-                    spawn_player(
-                        &mut commands,
-                        Vec3::new(
-                            transform.translation.x,
-                            0.0,
-                            rand::thread_rng().gen_range(-0.3..0.3),
-                        ),
-                        &skelet_asset,
-                        &shadow_asset,
-                        &player_settings,
-                        &mut sprite_params,
-                    );
-                }
+            if let Ok((entity, transform, deceased)) = deceased_query.get(x.entity) {
+                let position = Vec3::new(
+                    transform.translation.x,
+                    0.0,
+                    game_rng.rng.gen_range(-0.3..0.3),
+                );
+                let skeleton_info = skeleton_info_for(deceased);
+                let skeleton = spawn_player(
+                    &mut commands,
+                    position,
+                    &player_settings.for_corpse(&skeleton_info),
+                );
+                commands.entity(skeleton).insert(Raised {
+                    enemy_type: deceased.enemy_type.clone(),
+                    resurrections: deceased.resurrections + 1,
+                });
+                raised_events.send(SkeletonRaised {
+                    entity: skeleton,
+                    position,
+                    enemy_type: deceased.enemy_type.clone(),
+                });
                 commands.entity(entity).despawn();
             }
         }
@@ -231,27 +418,20 @@ fn arise_system(
 #[derive(Component)]
 pub struct Player;
 
+//sent for every skeleton arise brings back
+#[derive(Event)]
+pub struct SkeletonRaised {
+    pub entity: Entity,
+    pub position: Vec3,
+    //archetype of the corpse it was raised from
+    pub enemy_type: EnemyType,
+}
+
 pub fn spawn_player(
     mut commands: &mut Commands,
     pos: Vec3,
-    asset: &SkeletonSprite,
-    shadow: &ShadowSprite,
     player_settings: &PlayerSettings,
-    mut sprite3d_params: &mut Sprite3dParams,
-) {
-    let sprite = Sprite3dBuilder {
-        image: asset.image.clone(),
-        pixels_per_metre: 128.0,
-        alpha_mode: AlphaMode::Blend,
-        unlit: false,
-        pivot: Option::from(Vec2::new(0.35, 0.5)),
-        ..default()
-    };
-
-    let texture_atlas = TextureAtlas {
-        layout: asset.layout.clone(),
-        index: 0,
-    };
+) -> Entity {
     let mut player = commands.spawn((
         PlayerStateMaschine { attack_time: 0.15 },
         PlayerIdleState { new: true },
@@ -281,7 +461,7 @@ pub fn spawn_player(
                 },
             ],
         },
-        Transform::from_translation(pos),
+        Transform::from_translation(pos).with_scale(Vec3::splat(player_settings.scale)),
         RigidBody::Dynamic,
         Collider::circle(0.5),
         Controllable {
@@ -289,16 +469,16 @@ pub fn spawn_player(
         },
         get_player_collision_layers(),
         Direction { direction: -1.0 },
-        Opfer {
-            hit_layer: 1,
-            hits: VecDeque::new(),
-            knockback_multiplier: -1.0,
-        },
-        Health::from_health(1.0),
+        Opfer::new(1, 1.0, 0.0, 0.0),
+        Health::from_health(player_settings.health),
         Hitter {
             single: false,
+            radial: false,
             knockback: player_settings.knockback,
-            damage: player_settings.damage,
+            min_damage: player_settings.min_damage,
+            max_damage: player_settings.max_damage,
+            crit_chance: player_settings.crit_chance,
+            crit_multiplier: player_settings.crit_multiplier,
             hit_box: Vec2::new(1.0, 1.0),
             offset: Vec2::new(0.5, 0.0),
             hit_mask: 1,
@@ -307,55 +487,221 @@ pub fn spawn_player(
         LockedAxes::ROTATION_LOCKED,
         MassPropertiesBundle::from_shape(&Circle::new(0.5), 1.0),
     ));
-    player
-        .insert((
-            FancyWalk::default(),
-            Visibility::default(),
-            SceneObject,
-            Player,
-            Barrier {
-                min: BARRIER_MIN,
-                max: BARRIER_MAX,
-            },
-        ))
-        .with_children(|parent| {
+    player.insert((
+        FancyWalk::default(),
+        SkeletonAi::default(),
+        Visibility::default(),
+        SceneObject,
+        Player,
+        Barrier {
+            min: BARRIER_MIN,
+            max: BARRIER_MAX,
+        },
+    ));
+    return player.id();
+}
+
+//sprite and shadow for freshly raised skeletons, skipped when running headless
+fn attach_player_visuals_system(
+    mut commands: Commands,
+    asset: Res<SkeletonSprite>,
+    shadow: Res<ShadowSprite>,
+    mut sprite3d_params: Sprite3dParams,
+    player_query: Query<(&Transform, Entity), Added<Player>>,
+) {
+    for (transform, entity) in player_query.iter() {
+        let pos = transform.translation;
+        let sprite = Sprite3dBuilder {
+            image: asset.image.clone(),
+            pixels_per_metre: 128.0,
+            alpha_mode: AlphaMode::Blend,
+            unlit: false,
+            pivot: Option::from(Vec2::new(0.35, 0.5)),
+            ..default()
+        };
+
+        let texture_atlas = TextureAtlas {
+            layout: asset.layout.clone(),
+            index: 0,
+        };
+        commands.entity(entity).with_children(|parent| {
             parent.spawn((
                 sprite.bundle_with_atlas(&mut sprite3d_params, texture_atlas),
                 Transform::from_rotation(Quat::from_rotation_y(PI)),
             ));
         });
 
-    let shadow_sprite = Sprite3dBuilder {
-        image: shadow.image.clone(),
-        pixels_per_metre: 128.0,
-        alpha_mode: AlphaMode::Blend,
-        unlit: true,
-        ..default()
-    };
-    let player_id = player.id();
+        let shadow_sprite = Sprite3dBuilder {
+            image: shadow.image.clone(),
+            pixels_per_metre: 128.0,
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        };
 
-    commands.spawn((
-        SceneObject,
-        Transform::from_xyz(pos.x, -0.5, pos.z - 0.1).with_scale(Vec3::new(0.5, 0.1, 0.5)),
-        Shadow { caster: player_id },
-        shadow_sprite.bundle(&mut sprite3d_params),
-    ));
+        commands.spawn((
+            SceneObject,
+            Transform::from_xyz(pos.x, -0.5, pos.z - 0.1).with_scale(Vec3::new(0.5, 0.1, 0.5)),
+            Shadow { caster: entity },
+            shadow_sprite.bundle(&mut sprite3d_params),
+        ));
+    }
 }
 
+//used until the game data is there
+const DEFAULT_CORPSE_DECAY: f32 = 10.0;
+//part of the decay time after which the corpse starts to fade out
+const FADE_START: f32 = 0.6;
+
 #[derive(Component)]
 pub struct DeceasedSpawnPoint {
     pub enemy_type: EnemyType,
+    //how often this corpse was raised already
+    pub resurrections: u32,
+}
+//remembers what it was, raising it gives a skeleton of that kind
+#[derive(Component)]
+pub struct Deceased {
+    pub enemy_type: EnemyType,
+    pub resurrections: u32,
+    pub decay: Timer,
+}
+
+//on skeletons, so they can leave their corpse again
+#[derive(Component)]
+pub struct Raised {
+    pub enemy_type: EnemyType,
+    pub resurrections: u32,
+}
+
+//every dead enemy leaves a corpse behind, fallen skeletons too until they are used up
+fn leave_corpse_system(
+    mut commands: Commands,
+    mut killed_events: EventReader<EntityKilled>,
+    raised_query: Query<&Raised>,
+    arise_settings_query: Query<&AriseSettings>,
+) {
+    let max_resurrections = arise_settings_query
+        .get_single()
+        .map_or(1, |arise_settings| arise_settings.max_resurrections);
+    for killed in killed_events.read() {
+        let spawn_point = match (&killed.enemy_type, raised_query.get(killed.entity)) {
+            (Some(enemy_type), _) => DeceasedSpawnPoint {
+                enemy_type: enemy_type.clone(),
+                resurrections: 0,
+            },
+            //thrown off the edge, nothing left to raise
+            (None, Ok(raised)) if killed.cause != Cause::Out => {
+                if (raised.resurrections >= max_resurrections) {
+                    continue;
+                }
+                DeceasedSpawnPoint {
+                    enemy_type: raised.enemy_type.clone(),
+                    resurrections: raised.resurrections,
+                }
+            }
+            _ => continue,
+        };
+        commands.spawn((
+            SceneObject {},
+            spawn_point,
+            Transform::from_translation(killed.position),
+        ));
+    }
+}
+
+fn decay_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut deceased_query: Query<(&mut Deceased, Entity)>,
+) {
+    for (mut deceased, entity) in deceased_query.iter_mut() {
+        deceased.decay.tick(time.delta());
+        if (deceased.decay.finished()) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
 }
+
+//corpses share their material, so a fading one gets its own copy first
 #[derive(Component)]
-pub struct Deceased {}
+struct FadingMaterial;
+
+fn fade_deceased_system(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut deceased_query: Query<(
+        &Deceased,
+        &mut MeshMaterial3d<StandardMaterial>,
+        Has<FadingMaterial>,
+        Entity,
+    )>,
+) {
+    for (deceased, mut material, fading, entity) in deceased_query.iter_mut() {
+        let fraction = deceased.decay.fraction();
+        if (fraction < FADE_START) {
+            continue;
+        }
+        if (!fading) {
+            let Some(shared) = materials.get(&material.0) else {
+                continue;
+            };
+            let own = shared.clone();
+            material.0 = materials.add(own);
+            commands.entity(entity).insert(FadingMaterial);
+        }
+        if let Some(own) = materials.get_mut(&material.0) {
+            let alpha = 1.0 - (fraction - FADE_START) / (1.0 - FADE_START);
+            own.base_color.set_alpha(alpha.clamp(0.0, 1.0));
+        }
+    }
+}
 
 pub fn spawn_deceased(
     mut commands: Commands,
-    enemy_asset: Res<EnemySprite>,
-    mut sprite_params: Sprite3dParams,
+    game_data: Res<GameData>,
+    enemy_infos: Res<Assets<EnemyInfos>>,
     spawn_point_query: Query<(&Transform, &DeceasedSpawnPoint, Entity)>,
+    mut game_rng: ResMut<GameRng>,
+    arise_settings_query: Query<&AriseSettings>,
 ) {
+    let corpse_decay = arise_settings_query
+        .get_single()
+        .map_or(DEFAULT_CORPSE_DECAY, |arise_settings| {
+            arise_settings.corpse_decay
+        });
+    let Some(enemy_infos) = enemy_infos.get(game_data.enemies.id()) else {
+        return;
+    };
     for (transform, deceased, entity) in spawn_point_query.iter() {
+        let random = game_rng.rng.gen_range(-0.1..0.1);
+        let corpse_scale = enemy_infos
+            .archetypes
+            .get(&deceased.enemy_type.0)
+            .map_or(1.0, |archetype| archetype.corpse_scale);
+        commands.spawn((
+            SceneObject {},
+            Transform::from_translation(Vec3::new(transform.translation.x, -0.5, 0.5 + random))
+                .with_scale(Vec3::splat(corpse_scale))
+                .with_rotation(Quat::from_rotation_z(PI / 2.0)),
+            Deceased {
+                enemy_type: deceased.enemy_type.clone(),
+                resurrections: deceased.resurrections,
+                decay: Timer::from_seconds(corpse_decay, TimerMode::Once),
+            },
+        ));
+        commands.entity(entity).despawn();
+    }
+}
+
+//corpse sprite, skipped when running headless
+fn attach_deceased_visuals_system(
+    mut commands: Commands,
+    enemy_asset: Res<EnemySprite>,
+    mut sprite_params: Sprite3dParams,
+    deceased_query: Query<Entity, Added<Deceased>>,
+) {
+    for entity in deceased_query.iter() {
         let texture_atlas = TextureAtlas {
             layout: enemy_asset.layout.clone(),
             index: 0,
@@ -368,65 +714,9 @@ pub fn spawn_deceased(
             pivot: Option::from(Vec2::new(0.4, 0.5)),
             ..default()
         };
-        let random = rand::thread_rng().gen_range(-0.1..0.1);
-        match deceased.enemy_type {
-            EnemyType::BASIC => {
-                commands.spawn((
-                    SceneObject {},
-                    Transform::from_translation(Vec3::new(
-                        transform.translation.x,
-                        -0.5,
-                        0.5 + random,
-                    ))
-                    .with_rotation(Quat::from_rotation_z(PI / 2.0)),
-                    Deceased {},
-                    sprite.bundle_with_atlas(&mut sprite_params, texture_atlas.clone()),
-                ));
-            }
-            EnemyType::BIG => {
-                commands.spawn((
-                    SceneObject {},
-                    Transform::from_translation(Vec3::new(
-                        transform.translation.x,
-                        -0.5,
-                        0.5 + random,
-                    ))
-                    .with_scale(Vec3::splat(1.4))
-                    .with_rotation(Quat::from_rotation_z(PI / 2.0)),
-                    Deceased {},
-                    sprite.bundle_with_atlas(&mut sprite_params, texture_atlas.clone()),
-                ));
-            }
-            EnemyType::FAST => {
-                commands.spawn((
-                    SceneObject {},
-                    Transform::from_translation(Vec3::new(
-                        transform.translation.x,
-                        -0.5,
-                        0.5 + random,
-                    ))
-                    .with_scale(Vec3::splat(0.6))
-                    .with_rotation(Quat::from_rotation_z(PI / 2.0)),
-                    Deceased {},
-                    sprite.bundle_with_atlas(&mut sprite_params, texture_atlas.clone()),
-                ));
-            }
-            EnemyType::BOSS => {
-                commands.spawn((
-                    SceneObject {},
-                    Transform::from_translation(Vec3::new(
-                        transform.translation.x,
-                        -0.5,
-                        0.5 + random,
-                    ))
-                    .with_scale(Vec3::splat(2.0))
-                    .with_rotation(Quat::from_rotation_z(PI / 2.0)),
-                    Deceased {},
-                    sprite.bundle_with_atlas(&mut sprite_params, texture_atlas.clone()),
-                ));
-            }
-        }
-        commands.entity(entity).despawn();
+        commands
+            .entity(entity)
+            .insert(sprite.bundle_with_atlas(&mut sprite_params, texture_atlas));
     }
 }
 // pub fn die_system(mut commands: Commands, query: Query<Entity, (With<Dead>, With<Controllable>)>) {
@@ -1,26 +1,36 @@
-use crate::asset_load::{GameData, GameInfos, Messages, ShopItem, UIAssets, UISounds};
+use crate::asset_load::{level_index, GameData, GameInfos, Messages, ShopItem, UIAssets, UISounds};
 use crate::combat::Health;
 use crate::game_manager::Scorer;
+use crate::game_rng::GameRng;
 use crate::game_state::GameState::Shop;
 use crate::game_state::{GameState, PauseState};
 use crate::level_loading::SceneObject;
-use crate::state_handling::{get_sotred_value, store_value};
-use bevy::app::{App, Plugin, Update};
+use crate::state_handling::{
+    create_profile, delete_profile, load_profiles, load_save_data, load_settings, rename_profile,
+    select_profile, store_save_data, store_settings, SaveData, Settings, Upgrade,
+    MAX_PROFILE_NAME_LENGTH,
+};
+use crate::summoning::AriseSettings;
+use bevy::app::{App, Plugin, Startup, Update};
 use bevy::asset::{AssetServer, Assets};
 use bevy::audio::{AudioPlayer, PlaybackMode, PlaybackSettings};
 use bevy::color::palettes::css::CRIMSON;
 use bevy::color::Color;
 use bevy::ecs::system::lifetimeless::SCommands;
 use bevy::hierarchy::DespawnRecursiveExt;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
 use bevy::prelude::{
     default, in_state, AlignItems, BackgroundColor, BuildChildren, Button, Changed, ChildBuild,
-    ChildBuilder, Commands, Component, Entity, ImageNode, Interaction, IntoSystemConfigs,
-    JustifyContent, JustifyText, NextState, Node, OnEnter, Parent, Quat, Query, Res, ResMut, Text,
-    TextColor, TextFont, TextLayout, Time, Timer, Transform, UiRect, Val, With,
+    ChildBuilder, Commands, Component, Entity, EventReader, ImageNode, Interaction,
+    IntoSystemConfigs, JustifyContent, JustifyText, NextState, Node, OnEnter, Parent, Quat, Query,
+    Res, ResMut, Resource, Text, TextColor, TextFont, TextLayout, Time, Timer, Transform, UiRect,
+    Val, With,
 };
 use bevy::time::TimerMode;
 use bevy::ui::{FlexDirection, ZIndex};
 use bevy_pkv::PkvStore;
+use rand::Rng;
 use std::f32::consts::PI;
 use std::time::Duration;
 
@@ -37,6 +47,8 @@ impl Plugin for UIStuffPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(GameState::Shop), (setup_shop));
         app.add_systems(OnEnter(GameState::InGame), (setup_game_ui));
+        app.init_resource::<ProfileMenu>();
+        app.add_systems(Startup, load_settings_system);
         app.add_systems(OnEnter(GameState::Menu), (setup_main_menu));
         app.add_systems(OnEnter(GameState::Loading), (setup_loading_ui));
         app.add_systems(OnEnter(GameState::CompilingShaders), (setup_compiling_ui));
@@ -50,6 +62,7 @@ impl Plugin for UIStuffPlugin {
             Update,
             (
                 update_socre_display_system,
+                update_targeting_display_system,
                 setup_health_bar_system,
                 update_health_bar_system,
             )
@@ -57,7 +70,14 @@ impl Plugin for UIStuffPlugin {
         );
         app.add_systems(
             Update,
-            (button_system, menu_action).run_if(in_state(GameState::Menu)),
+            (
+                button_system,
+                menu_action,
+                profile_name_input_system,
+                setup_main_menu,
+            )
+                .chain()
+                .run_if(in_state(GameState::Menu)),
         );
 
         app.add_systems(
@@ -84,25 +104,42 @@ enum ShopButtonAction {
     ARISE_COOLDOWN,
     ARISE_COUNT,
     HIT_COOLDOWN,
+    CRIT_CHANCE,
+    CRIT_MULTIPLIER,
+    CORPSE_DECAY,
+    SWORD_DURABILITY,
+    SWORD_REPAIR,
 }
 fn buy(
-    shop_item: i32,
-    key: &str,
+    upgrade: Upgrade,
     game_data_res: &Res<GameData>,
     game_datas: &ResMut<Assets<GameInfos>>,
     pkv: &mut ResMut<PkvStore>,
 ) {
-    let curr_level = get_sotred_value(pkv, key);
+    let mut save_data = load_save_data(pkv);
+    let curr_level = save_data.level(upgrade);
     let Some(game_data) = game_datas.get(game_data_res.data.id()) else {
         return;
     };
-    let cost = game_data.shop_items[shop_item as usize].shop_displays[curr_level as usize].cost;
-
-    let curr_score = get_sotred_value(pkv, "score");
-    store_value(pkv, "score", curr_score - cost);
+    let level = shop_level(game_data, &save_data, upgrade);
+    let cost = game_data.shop_items[upgrade.shop_item()].shop_displays[level].cost;
+    //the last display is the maxed out one
+    if (cost < 0) {
+        return;
+    }
 
     //remove money
-    store_value(pkv, key, curr_level + 1);
+    save_data.score -= cost;
+    *save_data.level_mut(upgrade) = curr_level + 1;
+    store_save_data(pkv, &save_data);
+}
+
+//level clamped to the displays of the shop item, so a broken save shows the maxed out one
+fn shop_level(game_data: &GameInfos, save_data: &SaveData, upgrade: Upgrade) -> usize {
+    let displays = game_data.shop_items[upgrade.shop_item()]
+        .shop_displays
+        .len();
+    return level_index(displays, save_data.level(upgrade)).unwrap_or(0);
 }
 
 #[derive(Component)]
@@ -137,19 +174,50 @@ fn shop_action(
                     game_state.set(GameState::InGame);
                 }
                 ShopButtonAction::KNOCKBACK => {
-                    buy(0, "knockback", &game_data_res, &game_datas, &mut pkv)
+                    buy(Upgrade::Knockback, &game_data_res, &game_datas, &mut pkv)
                 }
 
-                ShopButtonAction::DAMAGE => buy(1, "damage", &game_data_res, &game_datas, &mut pkv),
-                ShopButtonAction::SPEED => buy(2, "speed", &game_data_res, &game_datas, &mut pkv),
-                ShopButtonAction::ARISE_COOLDOWN => {
-                    buy(3, "arise_cooldown", &game_data_res, &game_datas, &mut pkv)
+                ShopButtonAction::DAMAGE => {
+                    buy(Upgrade::Damage, &game_data_res, &game_datas, &mut pkv)
+                }
+                ShopButtonAction::SPEED => {
+                    buy(Upgrade::Speed, &game_data_res, &game_datas, &mut pkv)
                 }
+                ShopButtonAction::ARISE_COOLDOWN => buy(
+                    Upgrade::AriseCooldown,
+                    &game_data_res,
+                    &game_datas,
+                    &mut pkv,
+                ),
                 ShopButtonAction::ARISE_COUNT => {
-                    buy(4, "arise_count", &game_data_res, &game_datas, &mut pkv)
+                    buy(Upgrade::AriseCount, &game_data_res, &game_datas, &mut pkv)
+                }
+                ShopButtonAction::HIT_COOLDOWN => buy(
+                    Upgrade::AttackCooldown,
+                    &game_data_res,
+                    &game_datas,
+                    &mut pkv,
+                ),
+                ShopButtonAction::CRIT_CHANCE => {
+                    buy(Upgrade::CritChance, &game_data_res, &game_datas, &mut pkv)
                 }
-                ShopButtonAction::HIT_COOLDOWN => {
-                    buy(5, "attack_cooldown", &game_data_res, &game_datas, &mut pkv)
+                ShopButtonAction::CRIT_MULTIPLIER => buy(
+                    Upgrade::CritMultiplier,
+                    &game_data_res,
+                    &game_datas,
+                    &mut pkv,
+                ),
+                ShopButtonAction::CORPSE_DECAY => {
+                    buy(Upgrade::CorpseDecay, &game_data_res, &game_datas, &mut pkv)
+                }
+                ShopButtonAction::SWORD_DURABILITY => buy(
+                    Upgrade::SwordDurability,
+                    &game_data_res,
+                    &game_datas,
+                    &mut pkv,
+                ),
+                ShopButtonAction::SWORD_REPAIR => {
+                    buy(Upgrade::SwordRepair, &game_data_res, &game_datas, &mut pkv)
                 }
                 _ => {}
             }
@@ -273,7 +341,7 @@ fn setup_shop(
     };
 
     if let Ok(shop_screen) = shop_query.get_single() {
-        if let Ok(outdated) = outdated_query.get(shop_screen) {
+        if (outdated_query.get(shop_screen).is_ok()) {
             commands.entity(shop_screen).despawn_recursive();
         } else {
             return;
@@ -310,17 +378,33 @@ fn setup_shop(
             });
     }
 
-    let knockback_level = get_sotred_value(&mut pkv, "knockback");
-    let damage_level = get_sotred_value(&mut pkv, "damage");
-    let speed_level = get_sotred_value(&mut pkv, "speed");
-    let arise_cooldown_level = get_sotred_value(&mut pkv, "arise_cooldown");
-    let arise_count_level = get_sotred_value(&mut pkv, "arise_count");
-    let attack_cooldown_level = get_sotred_value(&mut pkv, "attack_cooldown");
-
-    let curr_score = get_sotred_value(&mut pkv, "score");
+    let save_data = load_save_data(&mut pkv);
+    let curr_score = save_data.score;
     let Some(game_data) = game_datas.get(game_data_res.data.id()) else {
         return;
     };
+    let knockback_level = shop_level(game_data, &save_data, Upgrade::Knockback);
+    let damage_level = shop_level(game_data, &save_data, Upgrade::Damage);
+    let speed_level = shop_level(game_data, &save_data, Upgrade::Speed);
+    let arise_cooldown_level = shop_level(game_data, &save_data, Upgrade::AriseCooldown);
+    let arise_count_level = shop_level(game_data, &save_data, Upgrade::AriseCount);
+    let attack_cooldown_level = shop_level(game_data, &save_data, Upgrade::AttackCooldown);
+    let crit_chance_level = shop_level(game_data, &save_data, Upgrade::CritChance);
+    let crit_multiplier_level = shop_level(game_data, &save_data, Upgrade::CritMultiplier);
+    let corpse_decay_level = shop_level(game_data, &save_data, Upgrade::CorpseDecay);
+    let sword_durability_level = shop_level(game_data, &save_data, Upgrade::SwordDurability);
+    let sword_repair_level = shop_level(game_data, &save_data, Upgrade::SwordRepair);
+    let knockback_item = &game_data.shop_items[Upgrade::Knockback.shop_item()];
+    let damage_item = &game_data.shop_items[Upgrade::Damage.shop_item()];
+    let speed_item = &game_data.shop_items[Upgrade::Speed.shop_item()];
+    let arise_cooldown_item = &game_data.shop_items[Upgrade::AriseCooldown.shop_item()];
+    let arise_count_item = &game_data.shop_items[Upgrade::AriseCount.shop_item()];
+    let attack_cooldown_item = &game_data.shop_items[Upgrade::AttackCooldown.shop_item()];
+    let crit_chance_item = &game_data.shop_items[Upgrade::CritChance.shop_item()];
+    let crit_multiplier_item = &game_data.shop_items[Upgrade::CritMultiplier.shop_item()];
+    let corpse_decay_item = &game_data.shop_items[Upgrade::CorpseDecay.shop_item()];
+    let sword_durability_item = &game_data.shop_items[Upgrade::SwordDurability.shop_item()];
+    let sword_repair_item = &game_data.shop_items[Upgrade::SwordRepair.shop_item()];
 
     commands
         .spawn((
@@ -363,42 +447,33 @@ fn setup_shop(
                         },
                     ));
 
-                    let knockback_cost =
-                        game_data.shop_items[0].shop_displays[knockback_level as usize].cost;
+                    let knockback_cost = knockback_item.shop_displays[knockback_level].cost;
                     get_shop_item(
                         parent,
-                        game_data.shop_items[0].name.clone(),
-                        game_data.shop_items[0].shop_displays[knockback_level as usize]
-                            .text
-                            .clone(),
+                        knockback_item.name.clone(),
+                        knockback_item.shop_displays[knockback_level].text.clone(),
                         knockback_cost,
                         ShopButtonAction::KNOCKBACK,
                         knockback_cost < 0,
                         knockback_cost > curr_score,
                     );
 
-                    let damage_cost =
-                        game_data.shop_items[1].shop_displays[damage_level as usize].cost;
+                    let damage_cost = damage_item.shop_displays[damage_level].cost;
                     get_shop_item(
                         parent,
-                        game_data.shop_items[1].name.clone(),
-                        game_data.shop_items[1].shop_displays[damage_level as usize]
-                            .text
-                            .clone(),
+                        damage_item.name.clone(),
+                        damage_item.shop_displays[damage_level].text.clone(),
                         damage_cost,
                         ShopButtonAction::DAMAGE,
                         damage_cost < 0,
                         damage_cost > curr_score,
                     );
 
-                    let speed_cost =
-                        game_data.shop_items[2].shop_displays[speed_level as usize].cost;
+                    let speed_cost = speed_item.shop_displays[speed_level].cost;
                     get_shop_item(
                         parent,
-                        game_data.shop_items[2].name.clone(),
-                        game_data.shop_items[2].shop_displays[speed_level as usize]
-                            .text
-                            .clone(),
+                        speed_item.name.clone(),
+                        speed_item.shop_displays[speed_level].text.clone(),
                         speed_cost,
                         ShopButtonAction::SPEED,
                         speed_cost < 0,
@@ -406,11 +481,11 @@ fn setup_shop(
                     );
 
                     let arise_cooldown_cost =
-                        game_data.shop_items[3].shop_displays[arise_cooldown_level as usize].cost;
+                        arise_cooldown_item.shop_displays[arise_cooldown_level].cost;
                     get_shop_item(
                         parent,
-                        game_data.shop_items[3].name.clone(),
-                        game_data.shop_items[3].shop_displays[arise_cooldown_level as usize]
+                        arise_cooldown_item.name.clone(),
+                        arise_cooldown_item.shop_displays[arise_cooldown_level]
                             .text
                             .clone(),
                         arise_cooldown_cost,
@@ -419,12 +494,11 @@ fn setup_shop(
                         arise_cooldown_cost > curr_score,
                     );
 
-                    let arise_count_cost =
-                        game_data.shop_items[4].shop_displays[arise_count_level as usize].cost;
+                    let arise_count_cost = arise_count_item.shop_displays[arise_count_level].cost;
                     get_shop_item(
                         parent,
-                        game_data.shop_items[4].name.clone(),
-                        game_data.shop_items[4].shop_displays[arise_count_level as usize]
+                        arise_count_item.name.clone(),
+                        arise_count_item.shop_displays[arise_count_level]
                             .text
                             .clone(),
                         arise_count_cost,
@@ -434,11 +508,11 @@ fn setup_shop(
                     );
 
                     let attack_cooldown_cost =
-                        game_data.shop_items[5].shop_displays[attack_cooldown_level as usize].cost;
+                        attack_cooldown_item.shop_displays[attack_cooldown_level].cost;
                     get_shop_item(
                         parent,
-                        game_data.shop_items[5].name.clone(),
-                        game_data.shop_items[5].shop_displays[attack_cooldown_level as usize]
+                        attack_cooldown_item.name.clone(),
+                        attack_cooldown_item.shop_displays[attack_cooldown_level]
                             .text
                             .clone(),
                         attack_cooldown_cost,
@@ -446,6 +520,75 @@ fn setup_shop(
                         attack_cooldown_cost < 0,
                         attack_cooldown_cost > curr_score,
                     );
+
+                    let crit_chance_cost = crit_chance_item.shop_displays[crit_chance_level].cost;
+                    get_shop_item(
+                        parent,
+                        crit_chance_item.name.clone(),
+                        crit_chance_item.shop_displays[crit_chance_level]
+                            .text
+                            .clone(),
+                        crit_chance_cost,
+                        ShopButtonAction::CRIT_CHANCE,
+                        crit_chance_cost < 0,
+                        crit_chance_cost > curr_score,
+                    );
+
+                    let crit_multiplier_cost =
+                        crit_multiplier_item.shop_displays[crit_multiplier_level].cost;
+                    get_shop_item(
+                        parent,
+                        crit_multiplier_item.name.clone(),
+                        crit_multiplier_item.shop_displays[crit_multiplier_level]
+                            .text
+                            .clone(),
+                        crit_multiplier_cost,
+                        ShopButtonAction::CRIT_MULTIPLIER,
+                        crit_multiplier_cost < 0,
+                        crit_multiplier_cost > curr_score,
+                    );
+
+                    let corpse_decay_cost =
+                        corpse_decay_item.shop_displays[corpse_decay_level].cost;
+                    get_shop_item(
+                        parent,
+                        corpse_decay_item.name.clone(),
+                        corpse_decay_item.shop_displays[corpse_decay_level]
+                            .text
+                            .clone(),
+                        corpse_decay_cost,
+                        ShopButtonAction::CORPSE_DECAY,
+                        corpse_decay_cost < 0,
+                        corpse_decay_cost > curr_score,
+                    );
+
+                    let sword_durability_cost =
+                        sword_durability_item.shop_displays[sword_durability_level].cost;
+                    get_shop_item(
+                        parent,
+                        sword_durability_item.name.clone(),
+                        sword_durability_item.shop_displays[sword_durability_level]
+                            .text
+                            .clone(),
+                        sword_durability_cost,
+                        ShopButtonAction::SWORD_DURABILITY,
+                        sword_durability_cost < 0,
+                        sword_durability_cost > curr_score,
+                    );
+
+                    let sword_repair_cost =
+                        sword_repair_item.shop_displays[sword_repair_level].cost;
+                    get_shop_item(
+                        parent,
+                        sword_repair_item.name.clone(),
+                        sword_repair_item.shop_displays[sword_repair_level]
+                            .text
+                            .clone(),
+                        sword_repair_cost,
+                        ShopButtonAction::SWORD_REPAIR,
+                        sword_repair_cost < 0,
+                        sword_repair_cost > curr_score,
+                    );
                 });
 
             parent
@@ -514,11 +657,93 @@ struct OnMainMenuScreen;
 #[derive(Component)]
 enum MenuButtonAction {
     Play,
+    SelectProfile(String),
+    NewProfile,
+    RenameProfile,
+    DeleteProfile,
+    ToggleDamageNumbers,
 }
 const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
 const DISABLED_TEXT_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
 
-fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+//name typed in the main menu for a new or renamed profile
+struct ProfileNaming {
+    renaming: bool,
+    text: String,
+}
+
+#[derive(Resource, Default)]
+struct ProfileMenu {
+    naming: Option<ProfileNaming>,
+    confirm_delete: bool,
+}
+
+fn get_menu_button(
+    parent: &mut ChildBuilder,
+    text: String,
+    action: MenuButtonAction,
+    selected: bool,
+    disabled: bool,
+) {
+    let button_node = Node {
+        width: Val::Vh(27.0),
+        height: Val::Vh(5.0),
+        margin: UiRect::all(Val::Vh(0.8)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let text_color = if disabled {
+        DISABLED_TEXT_COLOR
+    } else {
+        TEXT_COLOR
+    };
+    let background_color = if selected {
+        PRESSED_BUTTON
+    } else {
+        NORMAL_BUTTON
+    };
+    let mut button = parent.spawn((SceneObject, button_node, BackgroundColor(background_color)));
+    button.with_children(|parent| {
+        parent.spawn((
+            SceneObject,
+            Text::new(text),
+            TextFont {
+                font_size: 22.0,
+                ..default()
+            },
+            TextColor(text_color),
+        ));
+    });
+    if (!disabled) {
+        button.insert((Button, action));
+    }
+    if (selected) {
+        button.insert(SelectedOption);
+    }
+}
+
+fn load_settings_system(mut commands: Commands, mut pkv: ResMut<PkvStore>) {
+    commands.insert_resource(load_settings(&mut pkv));
+}
+
+fn setup_main_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut pkv: ResMut<PkvStore>,
+    profile_menu: Res<ProfileMenu>,
+    settings: Res<Settings>,
+    menu_query: Query<Entity, With<OnMainMenuScreen>>,
+    outdated_query: Query<Entity, With<Outdated>>,
+) {
+    if let Ok(menu_screen) = menu_query.get_single() {
+        if (outdated_query.get(menu_screen).is_ok()) {
+            commands.entity(menu_screen).despawn_recursive();
+        } else {
+            return;
+        }
+    }
+
     let button_node = Node {
         width: Val::Vh(27.0),
         height: Val::Vh(6.0),
@@ -533,6 +758,9 @@ fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
         ..default()
     };
 
+    let profiles = load_profiles(&mut pkv);
+    let busy = profile_menu.naming.is_some();
+
     commands
         .spawn((
             SceneObject,
@@ -540,12 +768,20 @@ fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                 width: Val::Percent(100.0),
                 height: Val::Percent(100.0),
                 align_items: AlignItems::Start,
-                justify_content: JustifyContent::Center,
+                justify_content: JustifyContent::SpaceBetween,
                 ..default()
             },
             OnMainMenuScreen,
         ))
         .with_children(|parent| {
+            //spacer so the title stays centered
+            parent.spawn((
+                SceneObject,
+                Node {
+                    width: Val::Vh(31.0),
+                    ..default()
+                },
+            ));
             parent
                 .spawn((
                     SceneObject,
@@ -572,10 +808,6 @@ fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                         },
                     ));
 
-                    // Display three buttons for each action available from the main menu:
-                    // - new game
-                    // - settings
-                    // - quit
                     parent
                         .spawn((
                             SceneObject,
@@ -594,6 +826,127 @@ fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                                 TextColor(TEXT_COLOR),
                             ));
                         });
+
+                    //settings
+                    let damage_numbers_text = if settings.damage_numbers {
+                        "Damage Numbers: On"
+                    } else {
+                        "Damage Numbers: Off"
+                    };
+                    get_menu_button(
+                        parent,
+                        damage_numbers_text.to_string(),
+                        MenuButtonAction::ToggleDamageNumbers,
+                        false,
+                        busy,
+                    );
+                });
+
+            //profiles
+            parent
+                .spawn((
+                    SceneObject,
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::all(Val::Vh(4.0)),
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        SceneObject,
+                        Text::new("Profiles"),
+                        TextFont {
+                            font_size: 32.0,
+                            ..default()
+                        },
+                        TextColor(TEXT_COLOR),
+                        Node {
+                            margin: UiRect::all(Val::Vh(2.0)),
+                            ..default()
+                        },
+                    ));
+                    for name in profiles.names.iter() {
+                        get_menu_button(
+                            parent,
+                            name.clone(),
+                            MenuButtonAction::SelectProfile(name.clone()),
+                            *name == profiles.current,
+                            busy,
+                        );
+                    }
+
+                    if let Some(naming) = &profile_menu.naming {
+                        let label = if naming.renaming {
+                            "Rename to:"
+                        } else {
+                            "New profile:"
+                        };
+                        parent.spawn((
+                            SceneObject,
+                            Text::new(format!("{}\n{}_", label, naming.text)),
+                            TextFont {
+                                font_size: 22.0,
+                                ..default()
+                            },
+                            TextLayout::new_with_justify(JustifyText::Center),
+                            TextColor(TEXT_COLOR),
+                            Node {
+                                margin: UiRect::all(Val::Vh(2.0)),
+                                ..default()
+                            },
+                        ));
+                        parent.spawn((
+                            SceneObject,
+                            Text::new("enter to confirm, esc to cancel"),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(DISABLED_TEXT_COLOR),
+                        ));
+                        return;
+                    }
+
+                    parent
+                        .spawn((
+                            SceneObject,
+                            Node {
+                                margin: UiRect::top(Val::Vh(2.0)),
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                        ))
+                        .with_children(|parent| {
+                            get_menu_button(
+                                parent,
+                                "New".to_string(),
+                                MenuButtonAction::NewProfile,
+                                false,
+                                false,
+                            );
+                            get_menu_button(
+                                parent,
+                                "Rename".to_string(),
+                                MenuButtonAction::RenameProfile,
+                                false,
+                                false,
+                            );
+                            let delete_text = if profile_menu.confirm_delete {
+                                format!("Delete {}?", profiles.current)
+                            } else {
+                                "Delete".to_string()
+                            };
+                            get_menu_button(
+                                parent,
+                                delete_text,
+                                MenuButtonAction::DeleteProfile,
+                                false,
+                                profiles.names.len() <= 1,
+                            );
+                        });
                 });
         });
 }
@@ -604,7 +957,11 @@ fn menu_action(
         (&Interaction, &MenuButtonAction),
         (Changed<Interaction>, With<Button>),
     >,
+    menu_query: Query<Entity, With<OnMainMenuScreen>>,
     mut game_state: ResMut<NextState<GameState>>,
+    mut profile_menu: ResMut<ProfileMenu>,
+    mut pkv: ResMut<PkvStore>,
+    mut settings: ResMut<Settings>,
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
@@ -615,11 +972,114 @@ fn menu_action(
                     ..Default::default()
                 },
             ));
+            if let Ok(menu_screen) = menu_query.get_single() {
+                commands.entity(menu_screen).insert(Outdated);
+            }
+            let confirm_delete = profile_menu.confirm_delete;
+            profile_menu.confirm_delete = false;
             match menu_button_action {
                 MenuButtonAction::Play => {
+                    profile_menu.naming = None;
                     game_state.set(GameState::InGame);
                 }
+                MenuButtonAction::SelectProfile(name) => {
+                    select_profile(&mut pkv, name);
+                }
+                MenuButtonAction::NewProfile => {
+                    profile_menu.naming = Some(ProfileNaming {
+                        renaming: false,
+                        text: String::new(),
+                    });
+                }
+                MenuButtonAction::RenameProfile => {
+                    profile_menu.naming = Some(ProfileNaming {
+                        renaming: true,
+                        text: load_profiles(&mut pkv).current,
+                    });
+                }
+                MenuButtonAction::DeleteProfile => {
+                    //first press only asks
+                    if (confirm_delete) {
+                        let current = load_profiles(&mut pkv).current;
+                        delete_profile(&mut pkv, &current);
+                    } else {
+                        profile_menu.confirm_delete = true;
+                    }
+                }
+                MenuButtonAction::ToggleDamageNumbers => {
+                    settings.damage_numbers = !settings.damage_numbers;
+                    store_settings(&mut pkv, &settings);
+                }
+            }
+        }
+    }
+}
+
+fn profile_name_input_system(
+    mut commands: Commands,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut profile_menu: ResMut<ProfileMenu>,
+    mut pkv: ResMut<PkvStore>,
+    menu_query: Query<Entity, With<OnMainMenuScreen>>,
+) {
+    let Some(naming) = profile_menu.naming.as_mut() else {
+        keyboard_events.clear();
+        return;
+    };
+    let mut changed = false;
+    let mut done = false;
+    for event in keyboard_events.read() {
+        if (event.state != ButtonState::Pressed) {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(characters) => {
+                for character in characters.chars() {
+                    if (!character.is_control()
+                        && naming.text.chars().count() < MAX_PROFILE_NAME_LENGTH)
+                    {
+                        naming.text.push(character);
+                        changed = true;
+                    }
+                }
+            }
+            Key::Space => {
+                if (naming.text.chars().count() < MAX_PROFILE_NAME_LENGTH) {
+                    naming.text.push(' ');
+                    changed = true;
+                }
+            }
+            Key::Backspace => {
+                changed |= naming.text.pop().is_some();
+            }
+            Key::Enter => {
+                let name = naming.text.trim().to_string();
+                let current = load_profiles(&mut pkv).current;
+                let accepted = if (naming.renaming) {
+                    name == current || rename_profile(&mut pkv, &current, &name)
+                } else {
+                    create_profile(&mut pkv, &name)
+                };
+                //keep typing if the name is taken or empty
+                if (accepted) {
+                    done = true;
+                }
+            }
+            Key::Escape => {
+                done = true;
             }
+            _ => {}
+        }
+        if (done) {
+            break;
+        }
+    }
+    if (done) {
+        profile_menu.naming = None;
+    }
+    if (changed || done) {
+        if let Ok(menu_screen) = menu_query.get_single() {
+            commands.entity(menu_screen).insert(Outdated);
         }
     }
 }
@@ -629,6 +1089,9 @@ fn menu_action(
 #[derive(Component)]
 struct ScoreDispay;
 
+#[derive(Component)]
+struct TargetingDisplay;
+
 #[derive(Component)]
 struct Preamble;
 
@@ -636,6 +1099,8 @@ struct Preamble;
 pub struct HealthBarInitiator {
     pub enity: Entity,
     pub name: String,
+    //bottom of the screen instead of the top, so the sword and the boss don't overlap
+    pub bottom: bool,
 }
 
 #[derive(Component)]
@@ -678,7 +1143,11 @@ fn setup_health_bar_system(
                     width: Val::Percent(100.0),
                     height: Val::Percent(100.0),
                     align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Start,
+                    justify_content: if (hbar.bottom) {
+                        JustifyContent::End
+                    } else {
+                        JustifyContent::Start
+                    },
                     ..default()
                 },
             ))
@@ -792,6 +1261,44 @@ fn setup_game_ui(mut commands: Commands) {
                 },
             ));
         });
+
+    commands
+        .spawn((
+            SceneObject,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Start,
+                justify_content: JustifyContent::Start,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                SceneObject,
+                TargetingDisplay,
+                Text::new(""),
+                text_font_smol.clone(),
+                TextColor(TEXT_COLOR),
+                Node {
+                    margin: UiRect::all(Val::Vh(4.0)),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn update_targeting_display_system(
+    arise_settings_query: Query<&AriseSettings>,
+    mut display_query: Query<&mut Text, With<TargetingDisplay>>,
+) {
+    let Ok(arise_settings) = arise_settings_query.get_single() else {
+        return;
+    };
+
+    for mut text in display_query.iter_mut() {
+        text.0 = format!("arise: {}", arise_settings.targeting.label());
+    }
 }
 
 fn update_socre_display_system(
@@ -884,6 +1391,7 @@ fn setup_cut_scene_ui(
     mut commands: Commands,
     ui_assets: Res<UIAssets>,
     death_messages: ResMut<Assets<Messages>>,
+    mut game_rng: ResMut<GameRng>,
 ) {
     let Some(messages) = death_messages.get(ui_assets.death_messages.id()) else {
         return;
@@ -905,7 +1413,7 @@ fn setup_cut_scene_ui(
         ))
         .with_children(|parent| {
             let message =
-                messages.messages[(rand::random::<usize>() % messages.messages.len())].to_string();
+                messages.messages[game_rng.rng.gen_range(0..messages.messages.len())].to_string();
             parent.spawn((
                 CompText,
                 SceneObject,